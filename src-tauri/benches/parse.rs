@@ -0,0 +1,79 @@
+//! Refresh path over a synthetic multi-month history
+//! Run with `cargo bench --bench parse`; fails when a step exceeds its time budget
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use chrono::{Duration, TimeZone, Utc};
+use claude_dashboard_lib::calculator::LimitPolicy;
+use claude_dashboard_lib::config::BlockSettings;
+use claude_dashboard_lib::{aggregate, create_blocks, parse_reader};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Entries in the synthetic history (about three months of heavy use)
+const ENTRIES: usize = 100_000;
+
+const MODELS: [&str; 3] = ["claude-opus-4-20250514", "claude-sonnet-4-20250514", "claude-3-5-haiku-20241022"];
+
+/// One Claude Code JSONL line per entry, a minute apart, 40 sessions
+fn synthetic_jsonl() -> String {
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    let mut out = String::new();
+    for i in 0..ENTRIES {
+        let ts = start + Duration::minutes(i as i64);
+        out.push_str(&format!(
+            r#"{{"timestamp":"{}","sessionId":"session-{}","message":{{"model":"{}","usage":{{"input_tokens":{},"output_tokens":{},"cache_creation_input_tokens":{},"cache_read_input_tokens":{}}}}}}}"#,
+            ts.to_rfc3339(),
+            i % 40,
+            MODELS[i % MODELS.len()],
+            100 + i % 500,
+            200 + i % 900,
+            i % 3000,
+            i % 20000,
+        ));
+        out.push('\n');
+    }
+    out
+}
+
+/// Budgets for one call over ENTRIES entries in a release build (about 10x the
+/// times measured when they were set: parse 190 ms, aggregate 10 ms, blocks 31 ms)
+const PARSE_BUDGET_MS: u128 = 2_000;
+const AGGREGATE_BUDGET_MS: u128 = 100;
+const BLOCKS_BUDGET_MS: u128 = 300;
+
+/// Best of a few runs must stay within `budget_ms`
+fn assert_budget<T>(name: &str, budget_ms: u128, mut run: impl FnMut() -> T) {
+    let best = (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(run());
+            start.elapsed().as_millis()
+        })
+        .min()
+        .unwrap_or_default();
+    assert!(best <= budget_ms, "{name} over {ENTRIES} entries took {best} ms (budget {budget_ms} ms)");
+}
+
+fn refresh(c: &mut Criterion) {
+    let jsonl = synthetic_jsonl();
+    let entries = parse_reader(jsonl.as_bytes());
+    assert_eq!(entries.len(), ENTRIES);
+    let policy = LimitPolicy::default();
+    let settings = BlockSettings::default();
+
+    assert_budget("parse_reader", PARSE_BUDGET_MS, || parse_reader(jsonl.as_bytes()));
+    assert_budget("aggregate", AGGREGATE_BUDGET_MS, || aggregate(&entries, "All Time", &policy));
+    assert_budget("create_blocks", BLOCKS_BUDGET_MS, || create_blocks(&entries, &settings, &policy));
+
+    c.bench_function("parse_reader 100k", |b| b.iter(|| parse_reader(black_box(jsonl.as_bytes()))));
+    c.bench_function("aggregate 100k", |b| b.iter(|| aggregate(black_box(&entries), "All Time", &policy)));
+    c.bench_function("create_blocks 100k", |b| b.iter(|| create_blocks(black_box(&entries), &settings, &policy)));
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = refresh
+}
+criterion_main!(benches);
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::CurrentBlockInfo;
+
+/// Usage thresholds (percent of plan limit) that trigger a notification
+pub const NOTIFY_THRESHOLDS: [f64; 2] = [80.0, 100.0];
+
+/// Tracks which thresholds already fired for the current block
+#[derive(Debug, Default)]
+pub struct ThresholdTracker {
+    /// Block the fired thresholds belong to
+    block_start: Option<DateTime<Utc>>,
+    /// Thresholds already notified for this block
+    fired: Vec<f64>,
+}
+
+impl ThresholdTracker {
+    /// Return thresholds newly crossed since the last check
+    /// A new block_start re-arms every threshold (usage resets with the block)
+    pub fn check(&mut self, block_start: Option<DateTime<Utc>>, usage_percent: f64) -> Vec<f64> {
+        if block_start != self.block_start {
+            self.block_start = block_start;
+            self.fired.clear();
+        }
+
+        // No active block = nothing to warn about
+        if block_start.is_none() {
+            return Vec::new();
+        }
+
+        let crossed: Vec<f64> = NOTIFY_THRESHOLDS
+            .iter()
+            .copied()
+            .filter(|t| usage_percent >= *t && !self.fired.contains(t))
+            .collect();
+        self.fired.extend(&crossed);
+        crossed
+    }
+}
+
+/// Percent of a plan limit at which dashboard warnings appear
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarningThresholds {
+    /// "Nearly exhausted" warning per limit
+    pub warn_percent: f64,
+    /// Rate-limited warning (cost or tokens)
+    pub critical_percent: f64,
+    /// Internal cost budget per block, below the plan cap (turns critical when reached)
+    pub soft_cost_limit: Option<f64>,
+    /// Internal token budget per block, below the plan cap
+    pub soft_token_limit: Option<u64>,
+}
+
+impl Default for WarningThresholds {
+    fn default() -> Self {
+        Self {
+            warn_percent: 90.0,
+            critical_percent: 100.0,
+            soft_cost_limit: None,
+            soft_token_limit: None,
+        }
+    }
+}
+
+/// Build the warning lines for the current block
+/// `ascii` swaps the emoji markers for [!] / [x]
+pub fn build_warnings(block: &CurrentBlockInfo, thresholds: &WarningThresholds, ascii: bool) -> Vec<String> {
+    let (warn_mark, critical_mark) = if ascii { ("[!]", "[x]") } else { ("⚠️", "🚨") };
+    let warn = thresholds.warn_percent;
+    let mut warnings = Vec::new();
+    if block.cost_percent >= warn {
+        warnings.push(format!("{} Cost limit nearly exhausted ({:.0}%+)", warn_mark, warn));
+    }
+    if block.tokens_percent >= warn {
+        warnings.push(format!("{} Token limit nearly exhausted ({:.0}%+)", warn_mark, warn));
+    }
+    if block.messages_percent >= warn {
+        warnings.push(format!("{} Message limit nearly exhausted ({:.0}%+)", warn_mark, warn));
+    }
+
+    let critical = thresholds.critical_percent;
+    if block.cost_percent >= critical || block.tokens_percent >= critical {
+        warnings.push(format!("{} RATE LIMITED ({:.0}%+) - Wait for reset!", critical_mark, critical));
+    }
+
+    // Per-tier caps can run out while the overall limit is fine
+    for tier in &block.tier_limits {
+        if tier.percent >= critical {
+            warnings.push(format!("{} {} limit reached ({:.0}%+)", critical_mark, tier.tier, critical));
+        } else if tier.percent >= warn {
+            warnings.push(format!("{} {} limit nearly exhausted ({:.0}%+)", warn_mark, tier.tier, warn));
+        }
+    }
+
+    if thresholds.soft_cost_limit.is_some_and(|soft| block.limit_cost >= soft) {
+        warnings.push(format!("{} Cost budget reached (soft limit)", critical_mark));
+    }
+    if thresholds.soft_token_limit.is_some_and(|soft| block.limit_tokens >= soft) {
+        warnings.push(format!("{} Token budget reached (soft limit)", critical_mark));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::models::TierLimitUsage;
+
+    #[test]
+    fn thresholds_fire_once_per_block_and_rearm_on_a_new_block() {
+        let first = Some(Utc.with_ymd_and_hms(2025, 3, 1, 10, 0, 0).unwrap());
+        let second = Some(Utc.with_ymd_and_hms(2025, 3, 1, 15, 0, 0).unwrap());
+        let mut tracker = ThresholdTracker::default();
+
+        assert!(tracker.check(first, 50.0).is_empty());
+        assert_eq!(tracker.check(first, 85.0), vec![80.0]);
+        assert!(tracker.check(first, 90.0).is_empty());
+        assert_eq!(tracker.check(first, 100.0), vec![100.0]);
+        assert!(tracker.check(first, 120.0).is_empty());
+
+        // Usage resets with the block, so both thresholds fire again
+        assert_eq!(tracker.check(second, 105.0), vec![80.0, 100.0]);
+        assert!(tracker.check(None, 105.0).is_empty());
+        assert_eq!(tracker.check(second, 80.0), vec![80.0]);
+    }
+
+    fn block(cost_percent: f64, tokens_percent: f64, messages_percent: f64) -> CurrentBlockInfo {
+        CurrentBlockInfo { cost_percent, tokens_percent, messages_percent, ..Default::default() }
+    }
+
+    #[test]
+    fn warnings_follow_custom_thresholds_per_limit() {
+        let thresholds = WarningThresholds { warn_percent: 75.0, critical_percent: 95.0, ..Default::default() };
+
+        assert_eq!(build_warnings(&block(76.0, 10.0, 10.0), &thresholds, false), ["⚠️ Cost limit nearly exhausted (75%+)"]);
+        assert_eq!(build_warnings(&block(10.0, 80.0, 10.0), &thresholds, false), ["⚠️ Token limit nearly exhausted (75%+)"]);
+        assert_eq!(build_warnings(&block(10.0, 10.0, 75.0), &thresholds, false), ["⚠️ Message limit nearly exhausted (75%+)"]);
+        assert!(build_warnings(&block(74.9, 74.9, 74.9), &thresholds, false).is_empty());
+
+        let critical = build_warnings(&block(96.0, 10.0, 10.0), &thresholds, false);
+        assert_eq!(critical.last().unwrap(), "🚨 RATE LIMITED (95%+) - Wait for reset!");
+        // Defaults stay at 90% / 100%
+        assert!(build_warnings(&block(85.0, 85.0, 85.0), &WarningThresholds::default(), false).is_empty());
+    }
+
+    #[test]
+    fn message_warning_needs_a_message_limit() {
+        let thresholds = WarningThresholds::default();
+        // Unlimited messages: the percentage stays at 0 however many calls
+        let unlimited = CurrentBlockInfo { limit_messages: 5_000, ..Default::default() };
+        assert!(build_warnings(&unlimited, &thresholds, false).is_empty());
+
+        let warnings = build_warnings(&block(10.0, 10.0, 100.0), &thresholds, false);
+        assert_eq!(warnings, ["⚠️ Message limit nearly exhausted (90%+)"]);
+    }
+
+    #[test]
+    fn ascii_warnings_have_no_emoji() {
+        let tier = |tier: &str, percent| TierLimitUsage { tier: tier.into(), percent, ..Default::default() };
+        let info = CurrentBlockInfo {
+            cost_percent: 100.0,
+            tokens_percent: 100.0,
+            messages_percent: 100.0,
+            limit_cost: 50.0,
+            limit_tokens: 50_000,
+            tier_limits: vec![tier("Opus", 100.0), tier("Sonnet", 95.0)],
+            ..Default::default()
+        };
+        let thresholds = WarningThresholds { soft_cost_limit: Some(10.0), soft_token_limit: Some(10_000), ..Default::default() };
+
+        let ascii = build_warnings(&info, &thresholds, true);
+        assert_eq!(ascii.len(), 8);
+        assert!(ascii.iter().all(|w| w.is_ascii()), "{:?}", ascii);
+        assert!(build_warnings(&info, &thresholds, false).iter().all(|w| !w.is_ascii()));
+    }
+
+    #[test]
+    fn soft_limits_turn_critical_below_the_plan_cap() {
+        // $26 and 60k tokens of a Max5 block: 74% / 68% of the hard limits
+        let info = CurrentBlockInfo { limit_cost: 26.0, cost_percent: 74.0, limit_tokens: 60_000, tokens_percent: 68.0, ..Default::default() };
+
+        let soft_cost = WarningThresholds { soft_cost_limit: Some(25.0), ..Default::default() };
+        assert_eq!(build_warnings(&info, &soft_cost, true), ["[x] Cost budget reached (soft limit)"]);
+        let soft_tokens = WarningThresholds { soft_token_limit: Some(50_000), ..Default::default() };
+        assert_eq!(build_warnings(&info, &soft_tokens, true), ["[x] Token budget reached (soft limit)"]);
+
+        let unreached = WarningThresholds { soft_cost_limit: Some(30.0), soft_token_limit: Some(80_000), ..Default::default() };
+        assert!(build_warnings(&info, &unreached, true).is_empty());
+    }
+}
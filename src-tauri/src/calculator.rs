@@ -1,4 +1,9 @@
-use crate::models::{Entry, ModelStats};
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Entry, ModelStats, Usage};
 
 /// Pricing per million tokens
 #[derive(Debug, Clone, Copy)]
@@ -30,12 +35,57 @@ impl Pricing {
         cache_create: 0.30,
         cache_read: 0.03,
     };
+
+    /// System entries cost nothing
+    pub const FREE: Pricing = Pricing {
+        input: 0.0,
+        output: 0.0,
+        cache_create: 0.0,
+        cache_read: 0.0,
+    };
+}
+
+/// Pricing-table cost of each token category of one entry, in dollars
+/// Computed once when the entry is parsed, so aggregation only sums floats
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenCosts {
+    pub input: f64,
+    pub output: f64,
+    pub cache_create: f64,
+    pub cache_read: f64,
+}
+
+impl TokenCosts {
+    pub fn new(model: &str, usage: &Usage) -> Self {
+        let pricing = get_pricing(model);
+        let million = 1_000_000.0;
+        Self {
+            input: (usage.input_tokens as f64 / million) * pricing.input,
+            output: (usage.output_tokens as f64 / million) * pricing.output,
+            cache_create: (usage.cache_creation_input_tokens as f64 / million) * pricing.cache_create,
+            cache_read: (usage.cache_read_input_tokens as f64 / million) * pricing.cache_read,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.input + self.output + self.cache_create + self.cache_read
+    }
+}
+
+/// Model name that system/cache-warming entries are bucketed under
+pub const SYSTEM_MODEL: &str = "(system)";
+
+/// Is this a synthetic or empty model name (system message, not a real call)?
+pub fn is_system_model(model: &str) -> bool {
+    model.is_empty() || model == "<synthetic>" || model == SYSTEM_MODEL
 }
 
 /// Get pricing for a model based on name
 pub fn get_pricing(model: &str) -> Pricing {
     let model_lower = model.to_lowercase();
-    if model_lower.contains("opus") {
+    if is_system_model(model) {
+        Pricing::FREE
+    } else if model_lower.contains("opus") {
         Pricing::OPUS
     } else if model_lower.contains("haiku") {
         Pricing::HAIKU
@@ -44,27 +94,69 @@ pub fn get_pricing(model: &str) -> Pricing {
     }
 }
 
-/// Get tier name for display
-pub fn get_tier(model: &str) -> &'static str {
+/// Get the model family, or None for names matching no known family
+pub fn known_tier(model: &str) -> Option<&'static str> {
     let model_lower = model.to_lowercase();
-    if model_lower.contains("opus") {
-        "Opus"
+    if is_system_model(model) {
+        Some("System")
+    } else if model_lower.contains("opus") {
+        Some("Opus")
     } else if model_lower.contains("haiku") {
-        "Haiku"
+        Some("Haiku")
+    } else if model_lower.contains("sonnet") {
+        Some("Sonnet")
     } else {
-        "Sonnet"
+        None
+    }
+}
+
+/// Get tier name for display ("Unknown" = priced as Sonnet, an estimate)
+pub fn get_tier(model: &str) -> &'static str {
+    known_tier(model).unwrap_or("Unknown")
+}
+
+/// Model name without the "claude-" prefix and date suffix ("claude-sonnet-4-20250514" -> "sonnet-4")
+pub fn short_model_name(model: &str) -> String {
+    let name = model.strip_prefix("claude-").unwrap_or(model);
+    match name.rsplit_once('-') {
+        Some((base, date)) if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) => base.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// Fixed display rank of a tier: Opus, Sonnet, Haiku, then Unknown and System
+pub fn tier_rank(tier: &str) -> u8 {
+    match tier {
+        "Opus" => 0,
+        "Sonnet" => 1,
+        "Haiku" => 2,
+        "System" => 4,
+        _ => 3,
     }
 }
 
 /// Get tier color for display (returns CSS color name)
 pub fn get_tier_color(model: &str) -> &'static str {
-    let model_lower = model.to_lowercase();
-    if model_lower.contains("opus") {
-        "magenta"
-    } else if model_lower.contains("haiku") {
-        "green"
-    } else {
-        "cyan"
+    match known_tier(model) {
+        Some("Opus") => "magenta",
+        Some("Haiku") => "green",
+        Some(_) => "cyan",
+        None => "yellow",
+    }
+}
+
+/// Models already reported as unknown (each is logged once per run)
+static UNKNOWN_MODELS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// Log a model matching no known family, once per model name
+pub fn warn_unknown_model(model: &str) {
+    if known_tier(model).is_some() {
+        return;
+    }
+    if let Ok(mut seen) = UNKNOWN_MODELS.lock() {
+        if seen.insert(model.to_string()) {
+            eprintln!("Unknown model \"{}\": cost estimated at Sonnet rates", model);
+        }
     }
 }
 
@@ -79,60 +171,146 @@ pub fn calculate_cost(stats: &ModelStats) -> f64 {
         + (stats.cache_read_tokens as f64 / million) * pricing.cache_read
 }
 
-/// Format token count with K/M suffix
-pub fn format_tokens(count: u64) -> String {
-    if count >= 1_000_000 {
-        format!("{:.1}M", count as f64 / 1_000_000.0)
-    } else if count >= 1_000 {
-        format!("{:.1}K", count as f64 / 1_000.0)
-    } else {
-        count.to_string()
+/// Token count display: K/M/B suffixes with `decimals` places, or raw grouped integers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TokenFormat {
+    /// Decimal places after the K/M/B suffix
+    pub decimals: usize,
+    /// Full integer with thousands separators instead of a suffix
+    pub raw: bool,
+}
+
+impl Default for TokenFormat {
+    fn default() -> Self {
+        Self { decimals: 1, raw: false }
+    }
+}
+
+/// Cost per 1M output tokens, 0.0 without output
+pub fn cost_per_output_mtok(cost: f64, output_tokens: u64) -> f64 {
+    if output_tokens == 0 {
+        return 0.0;
     }
+    cost / (output_tokens as f64 / 1_000_000.0)
 }
 
-/// Format cost as dollars
-pub fn format_cost(cost: f64) -> String {
-    if cost >= 100.0 {
-        format!("${:.0}", cost)
-    } else if cost >= 10.0 {
-        format!("${:.1}", cost)
+/// Amount with fewer decimals as it grows (2 below 10, 1 below 100, then none)
+fn compact_amount(amount: f64) -> String {
+    if amount >= 100.0 {
+        format!("{:.0}", amount)
+    } else if amount >= 10.0 {
+        format!("{:.1}", amount)
     } else {
-        format!("${:.2}", cost)
+        format!("{:.2}", amount)
+    }
+}
+
+/// Display currency: costs are computed in USD, then converted for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurrencySettings {
+    /// Symbol shown before the amount
+    pub symbol: String,
+    /// Units of this currency per US dollar
+    pub rate: f64,
+    /// Decimal separator ("." or ","); thousands use the other one
+    pub decimal_separator: String,
+}
+
+impl Default for CurrencySettings {
+    fn default() -> Self {
+        Self {
+            symbol: "$".into(),
+            rate: 1.0,
+            decimal_separator: ".".into(),
+        }
+    }
+}
+
+impl CurrencySettings {
+    /// Convert a USD cost and format it compactly (e.g. "€9.19") for CLI output;
+    /// the window formats its own values (formatMoney in App.tsx) with the same rules
+    pub fn format_money(&self, usd: f64) -> String {
+        let amount = compact_amount(usd * self.rate).replace('.', &self.decimal_separator);
+        format!("{}{}", self.symbol, amount)
+    }
+}
+
+/// Optional display of costs as abstract credits instead of dollars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CreditSettings {
+    /// Show credits instead of USD
+    pub enabled: bool,
+    /// Credits per dollar
+    pub per_dollar: f64,
+    /// Unit label shown after the amount
+    pub unit: String,
+}
+
+impl Default for CreditSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            per_dollar: 100.0,
+            unit: "credits".into(),
+        }
     }
 }
 
 /// Calculate FULL cost for a single entry (all tokens including cache)
+/// Uses the logged costUSD when present, otherwise the pricing table
 pub fn calculate_entry_cost(entry: &Entry) -> f64 {
-    let pricing = get_pricing(&entry.model);
-    let million = 1_000_000.0;
-    let u = &entry.usage;
-
-    (u.input_tokens as f64 / million) * pricing.input
-        + (u.output_tokens as f64 / million) * pricing.output
-        + (u.cache_creation_input_tokens as f64 / million) * pricing.cache_create
-        + (u.cache_read_input_tokens as f64 / million) * pricing.cache_read
+    entry.cost_usd.unwrap_or_else(|| entry.costs.total())
 }
 
-/// Calculate LIMIT cost for a single entry (input + output + cache_creation)
-/// This is what counts towards the rate limit
-/// Note: cache_read does NOT count (already cached), but cache_creation DOES
-pub fn calculate_entry_limit_cost(entry: &Entry) -> f64 {
-    let pricing = get_pricing(&entry.model);
-    let million = 1_000_000.0;
-    let u = &entry.usage;
+/// Calculate LIMIT cost for a single entry: the cost of the categories the policy counts
+/// (the same categories as get_limit_tokens, so both gauges measure the same usage)
+pub fn calculate_entry_limit_cost(entry: &Entry, policy: &LimitPolicy) -> f64 {
+    let c = &entry.costs;
+    let mut cost = c.output;
+    if policy.include_input {
+        cost += c.input;
+    }
+    if policy.include_cache_creation {
+        cost += c.cache_create;
+    }
+    if policy.include_cache_read {
+        cost += c.cache_read;
+    }
+    cost
+}
 
-    // input + output + cache_creation count towards the limit
-    // cache_read does NOT count (it's a discount, already in cache)
-    (u.input_tokens as f64 / million) * pricing.input
-        + (u.output_tokens as f64 / million) * pricing.output
-        + (u.cache_creation_input_tokens as f64 / million) * pricing.cache_create
+/// Which token categories count towards the limits, for tokens and cost alike
+/// Output always counts; real totals (real_tokens, real_cost) always sum everything
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct LimitPolicy {
+    /// Count input tokens
+    pub include_input: bool,
+    /// Count cache_creation tokens
+    pub include_cache_creation: bool,
+    /// Count cache_read tokens (usually the largest category)
+    pub include_cache_read: bool,
 }
 
-/// Get limit tokens - OUTPUT TOKENS ONLY
-/// Anthropic rate limits are based on OUTPUT tokens, not input
-/// This matches claude-monitor's calculation
-pub fn get_limit_tokens(entry: &Entry) -> u64 {
-    entry.usage.output_tokens
+/// Get limit tokens according to the policy
+/// Default: output only (claude-monitor's count); the built-in plan limits are output budgets,
+/// so widening the count needs a custom plan with limits scaled to match
+pub fn get_limit_tokens(entry: &Entry, policy: &LimitPolicy) -> u64 {
+    let u = &entry.usage;
+    let mut tokens = u.output_tokens;
+    if policy.include_input {
+        tokens = tokens.saturating_add(u.input_tokens);
+    }
+    if policy.include_cache_creation {
+        tokens = tokens.saturating_add(u.cache_creation_input_tokens);
+    }
+    if policy.include_cache_read {
+        tokens = tokens.saturating_add(u.cache_read_input_tokens);
+    }
+    tokens
 }
 
 /// Format duration in human readable format
@@ -153,3 +331,129 @@ pub fn format_duration(secs: i64) -> String {
         format!("{}s", secs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(model: &str, input: u64, output: u64, cache_create: u64, cache_read: u64) -> Entry {
+        let usage = Usage {
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_input_tokens: cache_create,
+            cache_read_input_tokens: cache_read,
+        };
+        Entry {
+            timestamp: chrono::Utc::now(),
+            session_id: "s1".into(),
+            costs: TokenCosts::new(model, &usage),
+            model: model.into(),
+            usage,
+            project: String::new(),
+            cost_usd: None,
+            source: Default::default(),
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn default_policy_counts_output_only_for_tokens_and_cost() {
+        // 1M of each category at Sonnet prices: input 3, output 15, cache write 3.75, cache read 0.30
+        let e = entry("claude-sonnet-4", 1_000_000, 2_000_000, 3_000_000, 4_000_000);
+        let policy = LimitPolicy::default();
+        assert_eq!(get_limit_tokens(&e, &policy), 2_000_000);
+        assert!(close(calculate_entry_limit_cost(&e, &policy), 30.0));
+    }
+
+    #[test]
+    fn tokens_and_cost_count_the_same_categories() {
+        let e = entry("claude-sonnet-4", 1_000_000, 2_000_000, 3_000_000, 4_000_000);
+        let policy = |include_input, include_cache_creation, include_cache_read| LimitPolicy {
+            include_input,
+            include_cache_creation,
+            include_cache_read,
+        };
+        assert_eq!(get_limit_tokens(&e, &policy(true, false, false)), 3_000_000);
+        assert!(close(calculate_entry_limit_cost(&e, &policy(true, false, false)), 33.0));
+        assert_eq!(get_limit_tokens(&e, &policy(true, true, false)), 6_000_000);
+        assert!(close(calculate_entry_limit_cost(&e, &policy(true, true, false)), 44.25));
+        assert_eq!(get_limit_tokens(&e, &policy(true, true, true)), e.usage.total());
+        assert!(close(calculate_entry_limit_cost(&e, &policy(true, true, true)), e.costs.total()));
+    }
+
+    #[test]
+    fn limit_tokens_saturate() {
+        let e = entry("claude-sonnet-4", u64::MAX, u64::MAX, 0, 0);
+        let policy = LimitPolicy { include_input: true, ..Default::default() };
+        assert_eq!(get_limit_tokens(&e, &policy), u64::MAX);
+    }
+
+    #[test]
+    fn format_money_drops_decimals_as_amounts_grow() {
+        let usd = CurrencySettings::default();
+        assert_eq!(usd.format_money(9.99), "$9.99");
+        assert_eq!(usd.format_money(10.0), "$10.0");
+        assert_eq!(usd.format_money(100.0), "$100");
+        assert_eq!(usd.format_money(1234.56), "$1235");
+    }
+
+    #[test]
+    fn format_money_converts_and_uses_the_decimal_separator() {
+        let eur = CurrencySettings { symbol: "€".into(), rate: 0.5, decimal_separator: ",".into() };
+        assert_eq!(eur.format_money(9.0), "€4,50");
+        assert_eq!(eur.format_money(30.0), "€15,0");
+
+        let dotted = CurrencySettings { symbol: "€".into(), rate: 0.92, decimal_separator: ".".into() };
+        assert_eq!(dotted.format_money(9.99), "€9.19");
+        assert_eq!(CurrencySettings { decimal_separator: ",".into(), ..dotted }.format_money(9.99), "€9,19");
+    }
+
+    #[test]
+    fn entry_cost_prefers_the_logged_cost() {
+        let computed = entry("claude-sonnet-4", 1_000_000, 1_000_000, 0, 0);
+        assert!(close(calculate_entry_cost(&computed), 18.0));
+
+        let logged = Entry { cost_usd: Some(17.5), ..computed };
+        assert!(close(calculate_entry_cost(&logged), 17.5));
+        // The limit cost stays on the pricing table
+        assert!(close(calculate_entry_limit_cost(&logged, &LimitPolicy::default()), 15.0));
+    }
+
+    #[test]
+    fn made_up_models_are_unknown_but_priced_as_sonnet() {
+        for model in ["claude-nimbus-5", "my-finetune"] {
+            assert_eq!(known_tier(model), None);
+            assert_eq!(get_tier(model), "Unknown");
+            assert_eq!(get_tier_color(model), "yellow");
+            let (unknown, sonnet) = (entry(model, 1_000_000, 1_000_000, 0, 0), entry("claude-sonnet-4", 1_000_000, 1_000_000, 0, 0));
+            assert!(close(calculate_entry_cost(&unknown), calculate_entry_cost(&sonnet)));
+        }
+        assert_eq!(get_tier("claude-sonnet-4-20250514"), "Sonnet");
+        assert_eq!(get_tier("Claude-OPUS-4"), "Opus");
+    }
+
+    #[test]
+    fn cache_toggles_add_exactly_their_own_cost() {
+        let e = entry("claude-opus-4", 1_000, 2_000, 300_000, 4_000_000);
+        let base = calculate_entry_limit_cost(&e, &LimitPolicy::default());
+        let with_read = calculate_entry_limit_cost(&e, &LimitPolicy { include_cache_read: true, ..Default::default() });
+        let with_creation = calculate_entry_limit_cost(&e, &LimitPolicy { include_cache_creation: true, ..Default::default() });
+
+        assert!(close(with_read - base, e.costs.cache_read));
+        assert!(close(with_creation - base, e.costs.cache_create));
+        assert!(e.costs.cache_read > 0.0 && e.costs.cache_create > 0.0);
+    }
+
+    #[test]
+    fn cache_writes_inflate_the_effective_output_price() {
+        // Sonnet lists output at $15/M; 4M cache writes add $15 on top
+        let heavy = entry("claude-sonnet-4", 0, 1_000_000, 4_000_000, 0);
+        assert!(close(cost_per_output_mtok(calculate_entry_cost(&heavy), heavy.usage.output_tokens), 30.0));
+        let plain = entry("claude-sonnet-4", 0, 2_000_000, 0, 0);
+        assert!(close(cost_per_output_mtok(calculate_entry_cost(&plain), plain.usage.output_tokens), 15.0));
+        assert_eq!(cost_per_output_mtok(5.0, 0), 0.0);
+    }
+}
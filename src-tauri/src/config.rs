@@ -0,0 +1,481 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use toml_edit::Document;
+
+use crate::alerts::WarningThresholds;
+use crate::calculator::{CreditSettings, CurrencySettings, LimitPolicy, TokenFormat};
+use crate::models::{PlanLimits, PLANS};
+
+/// Commented config written on first run (every key at its default)
+pub const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// User configuration (from ~/.claude/dashboard.toml)
+/// Every field has a default, so a missing file or missing keys are fine
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Parse only this JSONL file instead of scanning the data directory
+    pub data_file: Option<PathBuf>,
+    /// Anthropic console usage CSV merged into the history (periods before Claude Code)
+    pub console_csv: Option<PathBuf>,
+    /// File/directory names skipped while scanning projects/ ("archive/", "*.bak.jsonl")
+    pub scan_ignore: Vec<String>,
+    /// Directory depth under projects/ used as the project key (1 = immediate child)
+    pub project_depth: usize,
+    /// Group by the leaf directory containing the JSONL file instead of by depth
+    pub project_use_leaf: bool,
+    /// Per-category token count above which a log line is treated as corrupt and clamped
+    pub max_entry_tokens: u64,
+    /// Seconds within which the newest entry is flagged as a live call
+    pub live_window_secs: i64,
+    /// How entries are grouped into 5h blocks
+    pub blocks: BlockSettings,
+    /// Token categories that count towards the token limit
+    pub limit_policy: LimitPolicy,
+    /// Move to the next larger plan when block usage exceeds the selected one
+    pub auto_plan_switch: bool,
+    /// IANA timezone for days, weeks and hours (e.g. "Europe/Paris"); unset = system local
+    pub timezone: Zone,
+    /// Local hour at which a new day starts for today/week/month (0 = midnight)
+    pub day_start_hour: u32,
+    /// Per-day averages count today only for its elapsed part instead of as a full day
+    pub prorate_current_day: bool,
+    /// Number of most recent blocks kept for the history view
+    pub max_history_blocks: usize,
+    /// Percent of any limit at which a completed block counts as maxed out
+    pub maxed_threshold_percent: f64,
+    /// When the weekly usage window resets
+    pub week_reset: WeekReset,
+    /// "This Week" follows the week_reset window instead of the calendar week (Mon-Sun)
+    pub week_follows_reset: bool,
+    /// Address the metrics server listens on (loopback unless opted in)
+    pub metrics_bind: IpAddr,
+    /// Local hours counted for burn rate (default: the whole day)
+    pub work_hours: WorkHours,
+    /// Usage percentages at which dashboard warnings appear
+    pub warnings: WarningThresholds,
+    /// Show costs as credits instead of dollars
+    pub credits: CreditSettings,
+    /// Currency symbol, USD conversion rate and decimal separator for cost display
+    pub currency: CurrencySettings,
+    /// Token count display: decimals for K/M/B, or raw grouped integers
+    pub token_format: TokenFormat,
+    /// Tier breakdown order: "cost" (descending) or "family" (Opus > Sonnet > Haiku)
+    pub tier_order: TierOrder,
+    /// Keep synthetic/empty-model entries as a free "(system)" row (false = drop them)
+    pub show_system_entries: bool,
+    /// Replace emoji with ASCII markers (terminals/fonts without emoji)
+    pub ascii: bool,
+    /// Plan selected at startup, by name or index (overridden by --plan)
+    pub plan: Option<String>,
+    /// Extra plan with user-defined limits (e.g. a Team seat), appended after the built-in ones
+    pub custom_plan: Option<PlanLimits>,
+}
+
+/// Block grouping settings
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct BlockSettings {
+    /// Seconds after a block's end during which late entries still belong to it
+    /// and a block that was busy up to its end stays current
+    pub grace_secs: i64,
+    /// Start blocks at the first entry's exact time instead of the top of its hour
+    pub exact_start: bool,
+}
+
+impl Default for BlockSettings {
+    fn default() -> Self {
+        Self {
+            grace_secs: 10,
+            exact_start: false,
+        }
+    }
+}
+
+/// Weekly reset anchor (local day of week + hour) and the cost cap of that window
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct WeekReset {
+    pub weekday: Weekday,
+    pub hour: u32,
+    /// Cost cap in dollars for the window (0 = no cap, usage shown without percentage)
+    pub cost_limit: f64,
+}
+
+impl Default for WeekReset {
+    fn default() -> Self {
+        Self {
+            weekday: Weekday::Mon,
+            hour: 0,
+            cost_limit: 0.0,
+        }
+    }
+}
+
+/// Timezone used for local days and hours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Zone {
+    /// System local time
+    #[default]
+    Local,
+    /// Named IANA zone
+    Named(Tz),
+}
+
+impl TryFrom<String> for Zone {
+    type Error = String;
+
+    fn try_from(name: String) -> std::result::Result<Self, Self::Error> {
+        name.parse::<Tz>()
+            .map(Zone::Named)
+            .map_err(|_| format!("Unknown timezone: {}", name))
+    }
+}
+
+impl Zone {
+    /// Wall-clock time of `ts` in this zone
+    pub fn naive(&self, ts: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => ts.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => ts.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// UTC instant of a wall-clock time in this zone (earliest on DST overlap)
+    pub fn to_utc(&self, naive: NaiveDateTime) -> DateTime<Utc> {
+        let resolved = match self {
+            Zone::Local => Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+            Zone::Named(tz) => tz.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)),
+        };
+        resolved.unwrap_or_else(|| Utc.from_utc_datetime(&naive))
+    }
+
+    /// IANA name, None for system local
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Zone::Local => None,
+            Zone::Named(tz) => Some(tz.name().to_string()),
+        }
+    }
+}
+
+/// Order of the tier breakdown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TierOrder {
+    /// Most expensive tier first (ties in family order)
+    #[default]
+    Cost,
+    /// Fixed Opus > Sonnet > Haiku order
+    Family,
+}
+
+/// Local hour range `[start, end)`; wraps past midnight when start > end
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct WorkHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Default for WorkHours {
+    fn default() -> Self {
+        Self { start: 0, end: 24 }
+    }
+}
+
+impl WorkHours {
+    /// Does the range cover all 24 hours?
+    pub fn is_full_day(&self) -> bool {
+        self.start == 0 && self.end >= 24
+    }
+
+    /// Is this timestamp inside the work hours (in `zone`)?
+    pub fn contains(&self, ts: DateTime<Utc>, zone: Zone) -> bool {
+        let hour = zone.naive(ts).hour();
+        if self.start <= self.end {
+            hour >= self.start && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            data_file: None,
+            console_csv: None,
+            scan_ignore: Vec::new(),
+            project_depth: 1,
+            project_use_leaf: false,
+            max_entry_tokens: 10_000_000,
+            live_window_secs: 10,
+            blocks: BlockSettings::default(),
+            limit_policy: LimitPolicy::default(),
+            auto_plan_switch: false,
+            timezone: Zone::Local,
+            day_start_hour: 0,
+            prorate_current_day: false,
+            max_history_blocks: 50,
+            maxed_threshold_percent: 100.0,
+            week_reset: WeekReset::default(),
+            week_follows_reset: false,
+            metrics_bind: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            work_hours: WorkHours::default(),
+            warnings: WarningThresholds::default(),
+            credits: CreditSettings::default(),
+            currency: CurrencySettings::default(),
+            token_format: TokenFormat::default(),
+            tier_order: TierOrder::Cost,
+            show_system_entries: true,
+            ascii: false,
+            plan: None,
+            custom_plan: None,
+        }
+    }
+}
+
+impl Config {
+    /// Load config from disk, falling back to defaults when no file exists
+    pub fn load() -> Result<Self> {
+        config_path().map_or(Ok(Self::default()), |p| Self::load_at(&p))
+    }
+
+    fn load_at(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    /// Built-in plans followed by the custom plan, if configured
+    pub fn plans(&self) -> Vec<PlanLimits> {
+        let mut plans = PLANS.clone();
+        plans.extend(self.custom_plan.clone());
+        plans
+    }
+}
+
+/// Config cached until the file's mtime changes
+/// An edit that doesn't parse (e.g. saved mid-edit) keeps the previous good config
+pub struct ConfigReloader {
+    /// mtime of the file behind `loaded` (None = no file, defaults)
+    mtime: Option<SystemTime>,
+    loaded: Option<Config>,
+    /// When a changed file was last picked up (None = unchanged since startup)
+    pub reloaded_at: Option<DateTime<Utc>>,
+    /// Why the current file was rejected while the previous config stays in use
+    pub error: Option<String>,
+}
+
+impl ConfigReloader {
+    pub const fn new() -> Self {
+        Self {
+            mtime: None,
+            loaded: None,
+            reloaded_at: None,
+            error: None,
+        }
+    }
+
+    /// Did the file change since the cached config was read?
+    pub fn is_stale(&self) -> bool {
+        self.loaded.is_some() && config_mtime() != self.mtime
+    }
+
+    /// Current config, re-read first when the file changed
+    /// Errors only while no config was ever loaded
+    pub fn refresh(&mut self) -> Result<Config> {
+        self.refresh_at(config_path().as_deref())
+    }
+
+    fn refresh_at(&mut self, path: Option<&Path>) -> Result<Config> {
+        let mtime = path.and_then(file_mtime);
+        if let Some(config) = self.loaded.as_ref().filter(|_| mtime == self.mtime) {
+            return Ok(config.clone());
+        }
+
+        match (path.map_or(Ok(Config::default()), Config::load_at), &self.loaded) {
+            (Ok(config), previous) => {
+                if previous.is_some() {
+                    self.reloaded_at = Some(Utc::now());
+                }
+                self.loaded = Some(config.clone());
+                self.error = None;
+                self.mtime = mtime;
+                Ok(config)
+            }
+            (Err(e), Some(previous)) => {
+                // Remember the mtime so the broken file isn't re-parsed until saved again
+                let previous = previous.clone();
+                self.error = Some(format!("{:#}", e));
+                self.mtime = mtime;
+                Ok(previous)
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// Edit the config file (see update_config_file) and use the result right away
+    /// Not reported as a reload, since the dashboard made the change itself
+    pub fn update_file(&mut self, edit: impl FnOnce(&mut Document)) -> Result<Config> {
+        update_config_file(edit)?;
+        let config = Config::load()?;
+        self.loaded = Some(config.clone());
+        self.mtime = config_mtime();
+        self.error = None;
+        Ok(config)
+    }
+}
+
+impl Default for ConfigReloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Modification time of the config file, None when it doesn't exist
+fn config_mtime() -> Option<SystemTime> {
+    config_path().as_deref().and_then(file_mtime)
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok().and_then(|m| m.modified().ok())
+}
+
+/// Write the commented default config when no file exists yet
+/// Returns the path when a file was created
+pub fn write_default_config() -> Result<Option<PathBuf>> {
+    config_path().map_or(Ok(None), write_default_config_at)
+}
+
+fn write_default_config_at(path: PathBuf) -> Result<Option<PathBuf>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    }
+    // create_new fails atomically when the file exists, so a user's config is never overwritten
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Cannot create {}", path.display())),
+    };
+    file.write_all(DEFAULT_CONFIG.as_bytes())
+        .with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+/// Apply `edit` to the config file, keeping the user's comments and layout
+/// A missing file starts from the commented defaults; an unparsable one is left alone
+pub fn update_config_file(edit: impl FnOnce(&mut Document)) -> Result<PathBuf> {
+    let path = config_path().context("No home directory for the config file")?;
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DEFAULT_CONFIG.to_string(),
+        Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+    };
+    let mut doc: Document = content
+        .parse()
+        .with_context(|| format!("Invalid config in {}", path.display()))?;
+    edit(&mut doc);
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    }
+    std::fs::write(&path, doc.to_string()).with_context(|| format!("Cannot write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Get the config file path
+pub fn config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".claude").join("dashboard.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credits_are_off_unless_configured() {
+        assert!(!Config::default().credits.enabled);
+
+        let config: Config = toml::from_str("[credits]\nenabled = true\nper_dollar = 250.0\nunit = \"pts\"\n").unwrap();
+        assert!(config.credits.enabled);
+        assert_eq!(config.credits.per_dollar, 250.0);
+        assert_eq!(config.credits.unit, "pts");
+
+        // A partial section keeps the other defaults
+        let config: Config = toml::from_str("[credits]\nenabled = true\n").unwrap();
+        assert_eq!((config.credits.per_dollar, config.credits.unit.as_str()), (100.0, "credits"));
+    }
+
+    #[test]
+    fn default_config_is_written_only_when_absent_and_parses_back() {
+        let dir = std::env::temp_dir().join(format!("claude-dashboard-config-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("dashboard.toml");
+
+        let first = write_default_config_at(path.clone()).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, "day_start_hour = 4\n").unwrap();
+        let second = write_default_config_at(path.clone()).unwrap();
+        let kept = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(first, Some(path));
+        assert_eq!(second, None);
+        assert_eq!(kept, "day_start_hour = 4\n");
+        let parsed: Config = toml::from_str(&written).unwrap();
+        assert_eq!(format!("{:?}", parsed), format!("{:?}", Config::default()));
+    }
+
+    #[test]
+    fn token_format_defaults_to_one_decimal_suffixes() {
+        let format = Config::default().token_format;
+        assert_eq!((format.decimals, format.raw), (1, false));
+
+        let config: Config = toml::from_str("[token_format]\nraw = true\n").unwrap();
+        assert_eq!((config.token_format.decimals, config.token_format.raw), (1, true));
+        let config: Config = toml::from_str("[token_format]\ndecimals = 0\n").unwrap();
+        assert_eq!((config.token_format.decimals, config.token_format.raw), (0, false));
+    }
+
+    #[test]
+    fn changed_mtime_reloads_and_broken_edits_keep_the_last_good_config() {
+        let dir = std::env::temp_dir().join(format!("claude-dashboard-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dashboard.toml");
+        let save = |content: &str, secs: u64| {
+            std::fs::write(&path, content).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs)).unwrap();
+        };
+
+        let mut reloader = ConfigReloader::new();
+        save("day_start_hour = 2\n", 1_000);
+        assert_eq!(reloader.refresh_at(Some(&path)).unwrap().day_start_hour, 2);
+        assert!(reloader.reloaded_at.is_none());
+
+        save("day_start_hour = 4\n", 2_000);
+        assert_eq!(reloader.refresh_at(Some(&path)).unwrap().day_start_hour, 4);
+        assert!(reloader.reloaded_at.is_some());
+
+        save("day_start_hour = ", 3_000);
+        assert_eq!(reloader.refresh_at(Some(&path)).unwrap().day_start_hour, 4);
+        assert!(reloader.error.is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::calculator::{calculate_entry_cost, calculate_entry_limit_cost, get_limit_tokens, get_tier, LimitPolicy};
+use crate::config::Zone;
+use crate::models::SessionBlock;
+
+/// Per-tier totals inside an exported block
+#[derive(Debug, Clone, Serialize)]
+pub struct TierUsage {
+    pub tier: String,
+    pub calls: u64,
+    /// Cost towards limit
+    pub cost: f64,
+}
+
+/// One exported block (one JSONL line)
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockRecord {
+    pub start_time: DateTime<Utc>,
+    /// Block end time (= reset time)
+    pub end_time: DateTime<Utc>,
+    pub is_active: bool,
+    /// Cost towards limit
+    pub limit_cost: f64,
+    /// Real cost (all tokens including cache)
+    pub real_cost: f64,
+    /// Tokens towards limit
+    pub limit_tokens: u64,
+    pub calls: u64,
+    /// Per-tier breakdown, sorted by tier name
+    pub tiers: Vec<TierUsage>,
+}
+
+impl BlockRecord {
+    pub fn from_block(block: &SessionBlock, policy: &LimitPolicy) -> Self {
+        let mut tiers: BTreeMap<&str, (u64, f64)> = BTreeMap::new();
+        let mut limit_cost = 0.0;
+        let mut real_cost = 0.0;
+        let mut limit_tokens = 0u64;
+
+        for entry in &block.entries {
+            let cost = calculate_entry_limit_cost(entry, policy);
+            limit_cost += cost;
+            real_cost += calculate_entry_cost(entry);
+            limit_tokens = limit_tokens.saturating_add(get_limit_tokens(entry, policy));
+
+            let tier = tiers.entry(get_tier(&entry.model)).or_insert((0, 0.0));
+            tier.0 += 1;
+            tier.1 += cost;
+        }
+
+        Self {
+            start_time: block.start_time,
+            end_time: block.end_time,
+            is_active: block.is_active,
+            limit_cost,
+            real_cost,
+            limit_tokens,
+            calls: block.entries.len() as u64,
+            tiers: tiers
+                .into_iter()
+                .map(|(tier, (calls, cost))| TierUsage { tier: tier.to_string(), calls, cost })
+                .collect(),
+        }
+    }
+}
+
+/// Write one JSON object per block (JSON Lines)
+pub fn export_blocks_jsonl<W: Write>(
+    blocks: &[SessionBlock],
+    policy: &LimitPolicy,
+    mut writer: W,
+) -> io::Result<()> {
+    for block in blocks {
+        serde_json::to_writer(&mut writer, &BlockRecord::from_block(block, policy))?;
+        writeln!(writer)?;
+    }
+    writer.flush()
+}
+
+/// Header of the CSV block export
+pub const BLOCKS_CSV_HEADER: &str =
+    "start_time,start_local,end_time,end_local,is_active,limit_cost,limit_tokens,messages,models";
+
+/// Quote a CSV field when it holds a comma, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Write one CSV row per block after a header (just the header without blocks)
+/// Times are ISO 8601 in UTC plus a wall-clock column in `zone`; end = reset time,
+/// models = distinct model names in the block, sorted and separated by ";"
+pub fn export_blocks_csv<W: Write>(
+    blocks: &[SessionBlock],
+    policy: &LimitPolicy,
+    zone: Zone,
+    mut writer: W,
+) -> io::Result<()> {
+    let utc = |ts: DateTime<Utc>| ts.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let local = |ts: DateTime<Utc>| zone.naive(ts).format("%Y-%m-%dT%H:%M:%S").to_string();
+
+    writeln!(writer, "{}", BLOCKS_CSV_HEADER)?;
+    for block in blocks {
+        let record = BlockRecord::from_block(block, policy);
+        let models: BTreeSet<&str> = block.entries.iter().map(|e| e.model.as_str()).collect();
+        writeln!(
+            writer,
+            "{},{},{},{},{},{:.4},{},{},{}",
+            utc(record.start_time),
+            local(record.start_time),
+            utc(record.end_time),
+            local(record.end_time),
+            record.is_active,
+            record.limit_cost,
+            record.limit_tokens,
+            record.calls,
+            csv_field(&models.into_iter().collect::<Vec<_>>().join(";")),
+        )?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BlockSettings;
+    use crate::import::split_csv_line;
+    use crate::parser::create_blocks;
+    use crate::schema::map_str;
+
+    fn blocks() -> Vec<SessionBlock> {
+        let line = |ts: &str, model: &str, output: u64| {
+            map_str(&format!(
+                r#"{{"timestamp":"{ts}","sessionId":"s1","message":{{"model":"{model}","usage":{{"input_tokens":1000,"output_tokens":{output}}}}}}}"#
+            ))
+            .unwrap()
+        };
+        let entries = [
+            line("2025-03-01T10:15:00Z", "claude-sonnet-4", 2_000),
+            line("2025-03-01T11:00:00Z", "claude-opus-4", 500),
+            line("2025-03-01T11:30:00Z", "claude-sonnet-4", 1_000),
+            line("2025-03-01T18:00:00Z", "custom,model", 100),
+        ];
+        create_blocks(&entries, &BlockSettings::default(), &LimitPolicy::default())
+    }
+
+    #[test]
+    fn jsonl_has_one_record_per_block() {
+        let blocks = blocks();
+        let mut out = Vec::new();
+        export_blocks_jsonl(&blocks, &LimitPolicy::default(), &mut out).unwrap();
+        let records: Vec<serde_json::Value> =
+            String::from_utf8(out).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["start_time"], "2025-03-01T10:00:00Z");
+        assert_eq!(records[0]["end_time"], "2025-03-01T15:00:00Z");
+        assert_eq!(records[0]["is_active"], false);
+        assert_eq!(records[0]["calls"], 3);
+        assert_eq!(records[0]["limit_tokens"], 3_500);
+        assert!(records[0]["real_cost"].as_f64().unwrap() > records[0]["limit_cost"].as_f64().unwrap());
+        let tiers: Vec<&str> = records[0]["tiers"].as_array().unwrap().iter().map(|t| t["tier"].as_str().unwrap()).collect();
+        assert_eq!(tiers, ["Opus", "Sonnet"]);
+    }
+
+    #[test]
+    fn csv_round_trips_block_records() {
+        let blocks = blocks();
+        let policy = LimitPolicy::default();
+        let zone = Zone::Named(chrono_tz::Europe::Paris);
+        let mut out = Vec::new();
+        export_blocks_csv(&blocks, &policy, zone, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(BLOCKS_CSV_HEADER));
+        let rows: Vec<Vec<String>> = lines.map(split_csv_line).collect();
+        assert_eq!(rows.len(), blocks.len());
+
+        for (row, block) in rows.iter().zip(&blocks) {
+            let record = BlockRecord::from_block(block, &policy);
+            assert_eq!(row.len(), BLOCKS_CSV_HEADER.split(',').count());
+            assert_eq!(row[0].parse::<DateTime<Utc>>().unwrap(), record.start_time);
+            assert_eq!(row[2].parse::<DateTime<Utc>>().unwrap(), record.end_time);
+            assert_eq!(row[4], record.is_active.to_string());
+            assert!((row[5].parse::<f64>().unwrap() - record.limit_cost).abs() < 1e-4);
+            assert_eq!(row[6].parse::<u64>().unwrap(), record.limit_tokens);
+            assert_eq!(row[7].parse::<u64>().unwrap(), record.calls);
+        }
+
+        assert_eq!(rows[0][1], "2025-03-01T11:00:00");
+        assert_eq!(rows[0][6], "3500");
+        assert_eq!(rows[0][8], "claude-opus-4;claude-sonnet-4");
+        // The comma in the model name is quoted and comes back intact
+        assert_eq!(rows[1][8], "custom,model");
+    }
+
+    #[test]
+    fn csv_without_blocks_is_just_the_header() {
+        let mut out = Vec::new();
+        export_blocks_csv(&[], &LimitPolicy::default(), Zone::Local, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", BLOCKS_CSV_HEADER));
+    }
+}
@@ -0,0 +1,181 @@
+use std::io::BufRead;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::calculator::TokenCosts;
+use crate::config::Zone;
+use crate::models::{Entry, EntrySource, Usage};
+
+/// Session and project label shown for entries imported from a console export
+pub const CONSOLE_SESSION: &str = "console-import";
+
+/// Whether an entry came from a console export rather than a Claude Code log
+pub fn is_imported(entry: &Entry) -> bool {
+    entry.source == EntrySource::ConsoleImport
+}
+
+/// Split one CSV line, honouring double-quoted fields ("" = literal quote)
+pub(crate) fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Column of the first header matching one of `names` (case/space/underscore-insensitive)
+fn column(header: &[String], names: &[&str]) -> Option<usize> {
+    let normalize = |s: &str| s.trim().to_lowercase().replace([' ', '_'], "");
+    header.iter().position(|h| names.iter().any(|n| normalize(h) == normalize(n)))
+}
+
+/// Parse a date-only ("2025-03-01", local midnight in `zone`) or RFC 3339 timestamp
+fn parse_date(value: &str, zone: Zone) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|d| zone.to_utc(d))
+}
+
+/// Lines of `reader` without their line ending
+/// Lines that aren't valid UTF-8 are reported (1-based) and skipped instead of ending the read
+fn utf8_lines(mut reader: impl BufRead) -> impl Iterator<Item = String> {
+    let mut buf = Vec::new();
+    let mut line_no = 0;
+    std::iter::from_fn(move || loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return None,
+            Ok(_) => {
+                line_no += 1;
+                match std::str::from_utf8(&buf) {
+                    Ok(line) => return Some(line.trim_end_matches(['\n', '\r']).to_string()),
+                    Err(_) => eprintln!("Console CSV line {line_no} is not valid UTF-8, skipped"),
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                eprintln!("Console CSV read stopped after line {line_no}: {e}");
+                return None;
+            }
+        }
+    })
+}
+
+/// Parse a usage CSV exported from the Anthropic console into entries
+/// Needs date, model, input and output token columns; cache columns are optional (0)
+/// Rows that don't parse or carry no tokens are skipped
+/// Rows are day totals stamped at local midnight in `zone` (so they land on their own day),
+/// and are kept out of session blocks (see `is_imported`)
+pub fn parse_console_csv(reader: impl BufRead, zone: Zone) -> Vec<Entry> {
+    let mut lines = utf8_lines(reader);
+    let Some(header) = lines.next().map(|l| split_csv_line(l.trim_start_matches('\u{feff}'))) else {
+        return Vec::new();
+    };
+
+    let (Some(date_col), Some(model_col), Some(input_col), Some(output_col)) = (
+        column(&header, &["date", "usage_date", "timestamp"]),
+        column(&header, &["model", "model_name"]),
+        column(&header, &["input_tokens", "input"]),
+        column(&header, &["output_tokens", "output"]),
+    ) else {
+        eprintln!("Console CSV is missing a date, model, input_tokens or output_tokens column");
+        return Vec::new();
+    };
+    let cache_create_col = column(&header, &["cache_creation_input_tokens", "cache_creation_tokens", "cache_write_tokens"]);
+    let cache_read_col = column(&header, &["cache_read_input_tokens", "cache_read_tokens"]);
+
+    lines
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|line| {
+            let fields = split_csv_line(&line);
+            let tokens = |col: Option<usize>| -> u64 {
+                col.and_then(|c| fields.get(c))
+                    .and_then(|v| v.trim().replace(',', "").parse::<f64>().ok())
+                    .map_or(0, |v| v.max(0.0) as u64)
+            };
+            let usage = Usage {
+                input_tokens: tokens(Some(input_col)),
+                output_tokens: tokens(Some(output_col)),
+                cache_creation_input_tokens: tokens(cache_create_col),
+                cache_read_input_tokens: tokens(cache_read_col),
+            };
+            if usage.total() == 0 {
+                return None;
+            }
+
+            let model = fields.get(model_col)?.trim().to_string();
+            Some(Entry {
+                timestamp: parse_date(fields.get(date_col)?, zone)?,
+                session_id: CONSOLE_SESSION.into(),
+                costs: TokenCosts::new(&model, &usage),
+                model,
+                usage,
+                project: CONSOLE_SESSION.into(),
+                cost_usd: None,
+                source: EntrySource::ConsoleImport,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UTC: Zone = Zone::Named(chrono_tz::UTC);
+
+    #[test]
+    fn parses_console_rows() {
+        let csv = "\u{feff}Date,Model,Input Tokens,Output Tokens,Cache Read Tokens\n\
+                   2025-03-01,claude-sonnet-4,\"1,000\",200,50\n\
+                   2025-03-02T12:00:00Z,claude-opus-4,10,20,\n";
+        let entries = parse_console_csv(csv.as_bytes(), UTC);
+        assert_eq!(entries.len(), 2);
+
+        let first = &entries[0];
+        assert_eq!(first.timestamp.to_rfc3339(), "2025-03-01T00:00:00+00:00");
+        assert_eq!(first.usage.input_tokens, 1_000);
+        assert_eq!(first.usage.output_tokens, 200);
+        assert_eq!(first.usage.cache_read_input_tokens, 50);
+        assert_eq!(first.source, EntrySource::ConsoleImport);
+        assert!(is_imported(first));
+        assert_eq!(entries[1].usage.cache_read_input_tokens, 0);
+    }
+
+    #[test]
+    fn date_only_rows_start_at_local_midnight() {
+        let csv = "date,model,input_tokens,output_tokens\n\
+                   2025-03-01,claude-sonnet-4,1,1\n\
+                   2025-03-02T12:00:00Z,claude-sonnet-4,1,1\n";
+        let entries = parse_console_csv(csv.as_bytes(), Zone::Named(chrono_tz::Europe::Paris));
+        assert_eq!(entries[0].timestamp.to_rfc3339(), "2025-02-28T23:00:00+00:00");
+        // Full timestamps are kept as given
+        assert_eq!(entries[1].timestamp.to_rfc3339(), "2025-03-02T12:00:00+00:00");
+    }
+
+    #[test]
+    fn log_entries_are_not_imported_whatever_their_session() {
+        let line = r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"console-import","message":{"model":"claude-sonnet-4","usage":{"input_tokens":1,"output_tokens":1}}}"#;
+        let raw: crate::models::RawEntry = serde_json::from_str(line).unwrap();
+        let entry = Entry::try_from(raw).unwrap();
+        assert_eq!(entry.source, EntrySource::Log);
+        assert!(!is_imported(&entry));
+    }
+}
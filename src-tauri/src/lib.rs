@@ -1,7 +1,21 @@
+pub mod alerts;
 pub mod calculator;
+pub mod config;
+pub mod export;
+pub mod import;
+pub mod metrics;
 pub mod models;
 pub mod parser;
+pub mod schema;
+pub mod watcher;
 
 // Re-export for main.rs
-pub use models::{CurrentBlockInfo, DashboardData, ModelDistribution, PeriodStats, PlanLimits, PLANS};
-pub use parser::{aggregate, filter_this_month, filter_this_week, filter_today, get_current_block_info, get_model_distribution, parse_all};
+pub use alerts::{build_warnings, ThresholdTracker, WarningThresholds};
+pub use config::{write_default_config, Config, ConfigReloader};
+pub use export::{export_blocks_csv, export_blocks_jsonl, BlockRecord};
+pub use import::parse_console_csv;
+pub use metrics::{render_metrics, METRICS_CONTENT_TYPE};
+pub use models::{find_plan, BlockSummary, CurrentBlockInfo, DashboardData, EmptyState, Entry, EntrySource, ModelDistribution, PeriodStats, PlanLimits, ResetVerdict, SessionBlock, TierCost, TierFallbacks, TierLimitUsage, Trends, WeeklyBlockInfo, WeeklyLimitInfo, PLANS};
+pub use parser::{ParsedLogs, aggregate, aggregate_by_project, aggregate_by_session, average_days, block_hourly_tokens, clamp_future_entries, clamp_huge_entries, compute_trends, cost_by_weekday, create_blocks, create_recent_blocks, daily_cost_series, drop_system_entries, elapsed_days, elapsed_fractions, filter_last_week, filter_plan_week, filter_previous_plan_week, filter_this_month, filter_this_week, filter_today, filter_yesterday, find_maxed_blocks, fitting_plan_index, get_block_history, get_current_block_info, get_data_dir, get_model_distribution, get_model_version_distribution, get_weekly_block_info, get_weekly_limit_info, infer_plan, last_block_end, local_day, max_entry_cost, parse_all, parse_all_logs, parse_reader, parse_single_file, project_period_cost, recent_block_costs, reset_verdict, this_month_start, tier_breakdown, tier_fallbacks, usage_heatmap};
+pub use schema::{map_line, map_str, SchemaMapper, SCHEMAS};
+pub use watcher::LogWatcher;
@@ -1,71 +1,839 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use tauri::Manager;
+
 use claude_dashboard_lib::{
-    aggregate, filter_this_month, filter_this_week, filter_today,
-    get_current_block_info, get_model_distribution, parse_all,
-    DashboardData, PlanLimits, PLANS,
+    aggregate, aggregate_by_project, aggregate_by_session, build_warnings, clamp_future_entries,
+    clamp_huge_entries, compute_trends, cost_by_weekday, create_blocks, create_recent_blocks,
+    daily_cost_series, drop_system_entries, elapsed_days, elapsed_fractions, export_blocks_csv,
+    export_blocks_jsonl, filter_last_week, filter_plan_week, filter_previous_plan_week,
+    filter_this_month, filter_this_week, filter_today, filter_yesterday, find_maxed_blocks,
+    find_plan, fitting_plan_index, get_block_history, get_current_block_info, get_data_dir,
+    get_model_distribution, get_model_version_distribution, get_weekly_block_info,
+    get_weekly_limit_info, infer_plan, max_entry_cost, parse_all_logs, parse_reader,
+    recent_block_costs, render_metrics, this_month_start, tier_breakdown, tier_fallbacks,
+    usage_heatmap, write_default_config, Config, ConfigReloader, CurrentBlockInfo, DashboardData,
+    Entry, LogWatcher, ParsedLogs, PeriodStats, PlanLimits, SessionBlock, ThresholdTracker,
+    METRICS_CONTENT_TYPE,
 };
 
-/// Get all dashboard data for display
-#[tauri::command]
-fn get_dashboard_data(plan_index: usize) -> Result<DashboardData, String> {
-    let entries = parse_all().map_err(|e| e.to_string())?;
+/// Plan used when none is selected (Max5, same as the frontend default)
+const DEFAULT_PLAN_INDEX: usize = 1;
 
-    let plan_index = plan_index.min(PLANS.len().saturating_sub(1));
-    let selected_plan = PLANS.get(plan_index).cloned().unwrap_or_else(|| PlanLimits {
-        name: "Unknown".into(),
-        token_limit: 0,
-        cost_limit: 0.0,
-        message_limit: 0,
-    });
+/// Blocks shown in the cost-per-block sparkline
+const SPARKLINE_BLOCKS: usize = 12;
 
-    let today_entries = filter_today(&entries);
-    let week_entries = filter_this_week(&entries);
-    let month_entries = filter_this_month(&entries);
+/// Sessions listed in the most-expensive view
+const TOP_SESSIONS: usize = 10;
 
-    let current_block = get_current_block_info(&entries, &selected_plan);
-    let today = aggregate(&today_entries, "Today");
-    let week = aggregate(&week_entries, "This Week");
-    let month = aggregate(&month_entries, "This Month");
-    let model_distribution = get_model_distribution(&entries);
+/// Fraction the custom plan's limits move per +/- key press
+const CUSTOM_PLAN_STEP: f64 = 0.1;
 
-    // Generate warnings based on usage
-    let mut warnings = Vec::new();
-    if current_block.cost_percent >= 90.0 {
-        warnings.push("⚠️ Cost limit nearly exhausted (90%+)".to_string());
+/// Longest daily cost series the UI may request
+const MAX_DAILY_DAYS: usize = 365;
+
+/// Seconds between snapshots in --json mode
+const JSON_INTERVAL_SECS: u64 = 5;
+
+/// Seconds a metrics client gets to send its request line before it is dropped
+const METRICS_READ_TIMEOUT_SECS: u64 = 5;
+
+/// Plan index of the window's latest refresh, for the exit summary
+struct ActivePlan(AtomicUsize);
+
+impl ActivePlan {
+    fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
     }
-    if current_block.tokens_percent >= 90.0 {
-        warnings.push("⚠️ Token limit nearly exhausted (90%+)".to_string());
+
+    fn set(&self, plan_index: usize) {
+        self.0.store(plan_index, Ordering::Relaxed);
     }
-    if current_block.messages_percent >= 90.0 {
-        warnings.push("⚠️ Message limit nearly exhausted (90%+)".to_string());
+}
+
+/// Command-line options
+#[derive(Debug, Default)]
+struct CliArgs {
+    /// Headless JSON streaming instead of the window
+    json: bool,
+    /// Parse only this JSONL file
+    file: Option<PathBuf>,
+    /// Export per-block JSONL to this path ("-" = stdout) and exit
+    export_blocks: Option<String>,
+    /// Initial plan, by name or index
+    plan: Option<String>,
+    /// ASCII markers instead of emoji
+    ascii: bool,
+    /// Read entries from stdin once instead of scanning the data directory
+    stdin: bool,
+    /// Serve Prometheus metrics on this port instead of opening the window
+    metrics_port: Option<u16>,
+    /// Don't print the usage summary when the window closes
+    quiet: bool,
+    /// `today` subcommand: print today's stats once and exit
+    today: bool,
+}
+
+impl CliArgs {
+    /// Modes that print to the terminal instead of opening the window
+    fn headless(&self) -> bool {
+        self.json || self.today || self.export_blocks.is_some() || self.metrics_port.is_some()
     }
-    if current_block.cost_percent >= 100.0 || current_block.tokens_percent >= 100.0 {
-        warnings.push("🚨 RATE LIMITED - Wait for reset!".to_string());
+}
+
+/// Release builds on Windows use the GUI subsystem (no console window),
+/// so stdout/stderr go nowhere unless we attach to the launching terminal
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // SAFETY: no pointers involved; fails harmlessly without a parent console
+    // (redirected handles are inherited either way)
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
     }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+/// Parsed once at startup, read by every refresh
+static CLI: OnceLock<CliArgs> = OnceLock::new();
+
+/// Config file, re-read whenever it changes on disk
+static CONFIG: Mutex<ConfigReloader> = Mutex::new(ConfigReloader::new());
+
+/// Entries piped in with --stdin (a static snapshot)
+static STDIN_ENTRIES: OnceLock<Vec<Entry>> = OnceLock::new();
+
+/// Parse command-line arguments
+fn parse_args() -> Result<CliArgs, String> {
+    let mut cli = CliArgs::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => cli.json = true,
+            "--ascii" => cli.ascii = true,
+            "--stdin" => cli.stdin = true,
+            "--quiet" => cli.quiet = true,
+            "today" => cli.today = true,
+            "--file" => cli.file = Some(args.next().ok_or("--file needs a path")?.into()),
+            "--export-blocks" => cli.export_blocks = Some(args.next().ok_or("--export-blocks needs a path or -")?),
+            "--plan" => cli.plan = Some(args.next().ok_or("--plan needs a name or index")?),
+            "--metrics-port" => {
+                let port = args.next().ok_or("--metrics-port needs a port")?;
+                cli.metrics_port = Some(port.parse().map_err(|_| format!("Invalid port: {}", port))?);
+            }
+            other => return Err(format!("Unknown argument: {}", other)),
+        }
+    }
+
+    Ok(cli)
+}
+
+/// Load the config file (cached until it changes), then apply command-line overrides
+fn load_config() -> Result<Config, String> {
+    let mut config = CONFIG
+        .lock()
+        .map_err(|e| e.to_string())?
+        .refresh()
+        .map_err(|e| e.to_string())?;
+    if let Some(cli) = CLI.get() {
+        if cli.file.is_some() {
+            config.data_file = cli.file.clone();
+        }
+        if cli.ascii {
+            config.ascii = true;
+        }
+    }
+    Ok(config)
+}
+
+/// Parse the piped JSONL stream (sorted, future stamps clamped)
+fn read_stdin_entries() -> Vec<Entry> {
+    let mut entries: Vec<Entry> = parse_reader(std::io::stdin().lock())
+        .into_iter()
+        .map(|mut e| {
+            e.project = "stdin".into();
+            e
+        })
+        .collect();
+    entries.sort_by_key(|e| e.timestamp);
+    clamp_future_entries(&mut entries, Utc::now());
+    entries
+}
+
+/// Logs from stdin when piped, otherwise from disk
+fn load_logs(config: &Config) -> Result<ParsedLogs, String> {
+    let Some(entries) = STDIN_ENTRIES.get() else {
+        return parse_all_logs(config).map_err(|e| e.to_string());
+    };
+    let mut entries = entries.clone();
+    if !config.show_system_entries {
+        drop_system_entries(&mut entries);
+    }
+    clamp_huge_entries(&mut entries, config.max_entry_tokens);
+    Ok(ParsedLogs {
+        entries,
+        data_dir: Some(PathBuf::from("<stdin>")),
+        ..Default::default()
+    })
+}
+
+/// Entries from stdin or disk
+fn load_entries(config: &Config) -> Result<Vec<Entry>, String> {
+    load_logs(config).map(|logs| logs.entries)
+}
+
+/// Plan requested by --plan or the config file, if any
+fn initial_plan_index(config: &Config) -> Result<Option<usize>, String> {
+    let query = CLI.get().and_then(|cli| cli.plan.clone()).or(config.plan.clone());
+    let Some(query) = query else {
+        return Ok(None);
+    };
+
+    let plans = config.plans();
+    find_plan(&plans, &query).map(Some).ok_or_else(|| {
+        let names: Vec<&str> = plans.iter().map(|p| p.name.as_str()).collect();
+        format!("Unknown plan '{}' (available: {})", query, names.join(", "))
+    })
+}
+
+/// Only the newest block (enough for the current block, cheap to build every poll)
+fn latest_block(entries: &[Entry], config: &Config) -> Vec<SessionBlock> {
+    create_recent_blocks(entries, 1, &config.blocks, &config.limit_policy)
+}
+
+/// Current block for a plan index (clamped), applying the opt-in auto plan switch
+/// Returns the plan used, the plan switched away from (if any) and the block info
+fn current_block_for(
+    blocks: &[SessionBlock],
+    config: &Config,
+    plan_index: usize,
+) -> (PlanLimits, Option<String>, CurrentBlockInfo) {
+    let plans = config.plans();
+    let plan_index = plan_index.min(plans.len().saturating_sub(1));
+    let mut selected_plan = plans.get(plan_index).cloned().unwrap_or_else(|| PlanLimits {
+        name: "Unknown".into(),
+        ..Default::default()
+    });
+
+    let mut current_block = get_current_block_info(blocks, &selected_plan, config);
+
+    // Opt-in: move up to the smallest plan that still fits this block
+    let mut auto_switched_from = None;
+    if config.auto_plan_switch {
+        let fitting = fitting_plan_index(&plans, plan_index, &current_block);
+        if fitting != plan_index {
+            auto_switched_from = Some(selected_plan.name.clone());
+            selected_plan = plans[fitting].clone();
+            current_block = get_current_block_info(blocks, &selected_plan, config);
+        }
+    }
+
+    (selected_plan, auto_switched_from, current_block)
+}
+
+/// Build all dashboard data for a plan
+fn build_dashboard_data(plan_index: usize) -> Result<DashboardData, String> {
+    let config = load_config()?;
+    let (config_reloaded_at, config_error) = CONFIG
+        .lock()
+        .map(|reloader| (reloader.reloaded_at, reloader.error.clone()))
+        .unwrap_or_default();
+    let logs = load_logs(&config)?;
+    let empty_state = logs.empty_state();
+    let entries = logs.entries;
+
+    let today_entries = filter_today(&entries, config.day_start_hour, config.timezone);
+    let yesterday_entries = filter_yesterday(&entries, config.day_start_hour, config.timezone);
+    let (week_entries, week_label, last_week_entries, last_week_label) = if config.week_follows_reset {
+        (
+            filter_plan_week(&entries, &config.week_reset, config.timezone),
+            "Plan Week",
+            filter_previous_plan_week(&entries, &config.week_reset, config.timezone),
+            "Previous Plan Week",
+        )
+    } else {
+        (
+            filter_this_week(&entries, config.day_start_hour, config.timezone),
+            "This Week",
+            filter_last_week(&entries, config.day_start_hour, config.timezone),
+            "Last Week",
+        )
+    };
+    let month_entries = filter_this_month(&entries, config.day_start_hour, config.timezone);
+
+    let policy = &config.limit_policy;
+    // Every block helper below reads this one list instead of regrouping the entries
+    let blocks = create_blocks(&entries, &config.blocks, policy);
+
+    let (selected_plan, auto_switched_from, current_block) = current_block_for(&blocks, &config, plan_index);
+
+    let block_history = get_block_history(&blocks, config.max_history_blocks);
+    let block_costs = recent_block_costs(&blocks, SPARKLINE_BLOCKS);
+    let month_start = this_month_start(Utc::now(), config.day_start_hour, config.timezone);
+    let maxed_blocks = find_maxed_blocks(&blocks, &selected_plan, config.maxed_threshold_percent, month_start);
+    let weekly = get_weekly_block_info(&entries, &config.week_reset, config.timezone, policy);
+    let weekly_limits = get_weekly_limit_info(&entries, &selected_plan, policy);
+    let today = aggregate(&today_entries, "Today", policy);
+    let week = aggregate(&week_entries, week_label, policy);
+    let month = aggregate(&month_entries, "This Month", policy);
+    let all_time = aggregate(&entries, "All Time", policy);
+    let yesterday = aggregate(&yesterday_entries, "Yesterday", policy);
+    let last_week = aggregate(&last_week_entries, last_week_label, policy);
+    let week_reset = config.week_follows_reset.then_some(&config.week_reset);
+    let days = elapsed_days(Utc::now(), config.day_start_hour, config.timezone, week_reset);
+    let elapsed = elapsed_fractions(Utc::now(), config.day_start_hour, config.timezone);
+    let trends = compute_trends(&today, &week, &month, days, elapsed, config.prorate_current_day);
+    let model_distribution = get_model_distribution(&blocks, &selected_plan, policy);
+    let model_version_distribution = get_model_version_distribution(&blocks, &selected_plan, policy);
+    let projects = aggregate_by_project(&month_entries, policy);
+    let tier_breakdown = tier_breakdown(&month, config.tier_order);
+    let weekday_costs = cost_by_weekday(&month_entries, config.day_start_hour, config.timezone, policy);
+    let tier_fallbacks = tier_fallbacks(&month_entries);
+    let costliest_call_today = max_entry_cost(&today_entries);
+    let costliest_call_month = max_entry_cost(&month_entries);
+    let mut sessions = aggregate_by_session(&month_entries, policy);
+    sessions.truncate(TOP_SESSIONS);
+
+    // Generate warnings based on usage
+    let warnings = build_warnings(&current_block, &config.warnings, config.ascii);
 
     Ok(DashboardData {
         current_block,
         today,
         week,
         month,
+        all_time,
+        yesterday,
+        last_week,
         selected_plan,
+        auto_switched_from,
         model_distribution,
+        model_version_distribution,
+        block_history,
+        block_costs,
+        maxed_blocks,
+        weekly,
+        weekly_limits,
+        projects,
+        tier_breakdown,
+        weekday_costs,
+        tier_fallbacks,
+        sessions,
+        trends,
+        costliest_call_today,
+        costliest_call_month,
+        timezone: config.timezone.name(),
+        ascii: config.ascii,
+        thresholds: config.warnings,
+        empty_state,
+        skipped_files: logs.skipped_files,
+        snapshot: STDIN_ENTRIES.get().is_some(),
+        config_reloaded_at,
+        config_error,
+        credits: config.credits.enabled.then(|| config.credits.clone()),
+        currency: config.currency.clone(),
+        token_format: config.token_format,
         warnings,
     })
 }
 
+/// Get all dashboard data for display
+#[tauri::command]
+fn get_dashboard_data(
+    plan_index: usize,
+    tracker: tauri::State<'_, Mutex<ThresholdTracker>>,
+    active_plan: tauri::State<'_, ActivePlan>,
+) -> Result<DashboardData, String> {
+    let data = build_dashboard_data(plan_index)?;
+    active_plan.set(plan_index);
+
+    // Notify once per threshold per block
+    let block = &data.current_block;
+    let usage_percent = block.cost_percent.max(block.tokens_percent).max(block.messages_percent);
+    if let Ok(mut tracker) = tracker.lock() {
+        for threshold in tracker.check(block.block_start, usage_percent) {
+            notify_threshold(threshold);
+        }
+    }
+
+    Ok(data)
+}
+
+/// Show a desktop notification for a crossed usage threshold
+#[cfg(feature = "notifications")]
+fn notify_threshold(threshold: f64) {
+    let body = if threshold >= 100.0 {
+        "🚨 Block limit reached - wait for reset!".to_string()
+    } else {
+        format!("⚠️ Block usage crossed {:.0}%", threshold)
+    };
+    let _ = notify_rust::Notification::new()
+        .summary("Claude Dashboard")
+        .body(&body)
+        .show();
+}
+
+#[cfg(not(feature = "notifications"))]
+fn notify_threshold(_threshold: f64) {}
+
+/// Get only the current block (cheap enough to poll every second)
+#[tauri::command]
+fn get_block_prediction(plan_index: usize) -> Result<CurrentBlockInfo, String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    Ok(current_block_for(&latest_block(&entries, &config), &config, plan_index).2)
+}
+
+/// Get the plan set by --plan or the config (None = keep the stored choice)
+#[tauri::command]
+fn get_initial_plan() -> Result<Option<usize>, String> {
+    initial_plan_index(&load_config()?)
+}
+
+/// Guess the plan from past block usage (None = not enough signal)
+#[tauri::command]
+fn get_inferred_plan() -> Result<Option<usize>, String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+    Ok(infer_plan(&config.plans(), &blocks))
+}
+
+/// Did a log or the config change since the last call? None = no watcher, rely on the refresh interval
+#[tauri::command]
+fn take_logs_changed(watcher: tauri::State<'_, Option<LogWatcher>>) -> Option<bool> {
+    let config_changed = CONFIG.lock().is_ok_and(|reloader| reloader.is_stale());
+    watcher.as_ref().map(|w| w.take_dirty() || config_changed)
+}
+
+/// Start watching the logs the dashboard reads (none for a --stdin snapshot)
+fn start_log_watcher() -> Option<LogWatcher> {
+    if STDIN_ENTRIES.get().is_some() {
+        return None;
+    }
+    let path = match load_config().ok().and_then(|c| c.data_file) {
+        Some(file) => file,
+        None => get_data_dir().filter(|dir| dir.exists())?,
+    };
+    LogWatcher::start(&path)
+}
+
+/// Remember the selected plan in the config file (`plan` key, restored on the next launch)
+#[tauri::command]
+fn save_selected_plan(plan_index: usize) -> Result<(), String> {
+    let plans = load_config()?.plans();
+    let name = plans
+        .get(plan_index)
+        .map(|p| p.name.clone())
+        .ok_or_else(|| format!("Unknown plan index {}", plan_index))?;
+    CONFIG
+        .lock()
+        .map_err(|e| e.to_string())?
+        .update_file(|doc| doc["plan"] = toml_edit::value(name))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Scale the custom plan's block limits one step up (direction > 0) or down (< 0) and save
+/// them to [custom_plan]; without a custom plan, it starts from the selected plan's limits
+/// Returns the custom plan's index
+#[tauri::command]
+fn adjust_custom_plan(plan_index: usize, direction: i32) -> Result<usize, String> {
+    let config = load_config()?;
+    let plans = config.plans();
+    let base = match &config.custom_plan {
+        Some(custom) => custom.clone(),
+        None => plans
+            .get(plan_index)
+            .cloned()
+            .ok_or_else(|| format!("Unknown plan index {}", plan_index))?,
+    };
+
+    let factor = match direction.signum() {
+        1 => 1.0 + CUSTOM_PLAN_STEP,
+        -1 => 1.0 - CUSTOM_PLAN_STEP,
+        _ => return Err("Direction must be positive (up) or negative (down)".into()),
+    };
+    let scaled = base.scaled(factor);
+    let is_new = config.custom_plan.is_none();
+
+    let config = CONFIG
+        .lock()
+        .map_err(|e| e.to_string())?
+        .update_file(|doc| {
+            if !doc.contains_table("custom_plan") {
+                doc["custom_plan"] = toml_edit::table();
+            }
+            let table = &mut doc["custom_plan"];
+            if is_new {
+                table["name"] = toml_edit::value("Custom");
+            }
+            table["token_limit"] = toml_edit::value(scaled.token_limit as i64);
+            table["cost_limit"] = toml_edit::value(scaled.cost_limit);
+            table["message_limit"] = toml_edit::value(scaled.message_limit as i64);
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(config.plans().len() - 1)
+}
+
 /// Get available plans for selection
 #[tauri::command]
-fn get_available_plans() -> Vec<PlanLimits> {
-    PLANS.clone()
+fn get_available_plans() -> Result<Vec<PlanLimits>, String> {
+    Ok(load_config()?.plans())
+}
+
+/// Get limit cost in cents by local weekday (0 = Mon) and hour
+#[tauri::command]
+fn get_usage_heatmap() -> Result<[[u64; 24]; 7], String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    Ok(usage_heatmap(&entries, config.timezone, &config.limit_policy))
+}
+
+/// Get limit cost per local day for the last `days` days (oldest first, 1..=MAX_DAILY_DAYS)
+#[tauri::command]
+fn get_daily_costs(days: usize) -> Result<Vec<(NaiveDate, f64)>, String> {
+    let days = days.clamp(1, MAX_DAILY_DAYS);
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    Ok(daily_cost_series(&entries, days, config.day_start_hour, config.timezone, &config.limit_policy))
+}
+
+/// Get every block as CSV (header + one row per block, oldest first)
+#[tauri::command]
+fn get_blocks_csv() -> Result<String, String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+
+    let mut csv = Vec::new();
+    export_blocks_csv(&blocks, &config.limit_policy, config.timezone, &mut csv).map_err(|e| e.to_string())?;
+    String::from_utf8(csv).map_err(|e| e.to_string())
+}
+
+/// Write every block to `target` ("-" = stdout): CSV for a .csv path, otherwise one JSON line each
+fn run_export_blocks(target: &str) -> Result<(), String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+
+    let export = |writer: &mut dyn Write| {
+        if target.to_lowercase().ends_with(".csv") {
+            export_blocks_csv(&blocks, &config.limit_policy, config.timezone, writer)
+        } else {
+            export_blocks_jsonl(&blocks, &config.limit_policy, writer)
+        }
+    };
+    let result = if target == "-" {
+        export(&mut std::io::stdout().lock())
+    } else {
+        let file = std::fs::File::create(target).map_err(|e| format!("Cannot create {}: {}", target, e))?;
+        export(&mut std::io::BufWriter::new(file))
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// Headless mode: print a DashboardData JSON line every few seconds until interrupted
+fn run_json_mode(plan_index: usize) {
+    let mut stdout = std::io::stdout();
+    loop {
+        let snapshot = build_dashboard_data(plan_index)
+            .and_then(|d| serde_json::to_string(&d).map_err(|e| e.to_string()));
+        match snapshot {
+            Ok(json) => {
+                let _ = writeln!(stdout, "{}", json);
+                let _ = stdout.flush();
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+        std::thread::sleep(Duration::from_secs(JSON_INTERVAL_SECS));
+    }
+}
+
+/// Current block + today's stats rendered for a /metrics scrape
+fn metrics_snapshot(plan_index: usize) -> Result<String, String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    let (_, _, block) = current_block_for(&latest_block(&entries, &config), &config, plan_index);
+    let today_entries = filter_today(&entries, config.day_start_hour, config.timezone);
+    let today = aggregate(&today_entries, "Today", &config.limit_policy);
+    Ok(render_metrics(&block, &today))
+}
+
+/// One-line usage summary, e.g. "Today: $4.20 across 37 calls, block resets at 16:00"
+fn format_exit_summary(today: &PeriodStats, block: &CurrentBlockInfo, config: &Config) -> String {
+    let reset = match block.reset_time {
+        Some(reset) if block.is_active => format!("block resets at {}", config.timezone.naive(reset).format("%H:%M")),
+        _ => "no active block".to_string(),
+    };
+    format!(
+        "Today: {} across {} calls, {}",
+        config.currency.format_money(today.limit_cost),
+        today.total_calls,
+        reset
+    )
+}
+
+/// Summary printed to the terminal once the window has closed
+fn exit_summary(plan_index: usize) -> Result<String, String> {
+    let config = load_config()?;
+    let entries = load_entries(&config)?;
+    let (_, _, block) = current_block_for(&latest_block(&entries, &config), &config, plan_index);
+    let today_entries = filter_today(&entries, config.day_start_hour, config.timezone);
+    let today = aggregate(&today_entries, "Today", &config.limit_policy);
+    Ok(format_exit_summary(&today, &block, &config))
+}
+
+/// Compact JSON for status bars: today's limit cost/tokens/calls, block reset and usage percent
+fn today_json(today: &PeriodStats, block: &CurrentBlockInfo) -> serde_json::Value {
+    serde_json::json!({
+        "cost": today.limit_cost,
+        "tokens": today.limit_tokens,
+        "calls": today.total_calls,
+        "block_reset": block.reset_time.filter(|_| block.is_active),
+        "percent": block.cost_percent.max(block.tokens_percent).max(block.messages_percent),
+    })
+}
+
+/// `today` subcommand: one summary line (or JSON with --json), exit 1 on errors
+fn run_today(plan_index: usize, json: bool) -> i32 {
+    let stats = load_config().and_then(|config| {
+        let entries = load_entries(&config)?;
+        let (_, _, block) = current_block_for(&latest_block(&entries, &config), &config, plan_index);
+        let today_entries = filter_today(&entries, config.day_start_hour, config.timezone);
+        let today = aggregate(&today_entries, "Today", &config.limit_policy);
+        Ok((today, block, config))
+    });
+
+    match (stats, json) {
+        (Ok((today, block, _)), true) => println!("{}", today_json(&today, &block)),
+        (Ok((today, block, config)), false) => println!("{}", format_exit_summary(&today, &block, &config)),
+        (Err(e), true) => {
+            println!("{}", serde_json::json!({ "error": e }));
+            return 1;
+        }
+        (Err(e), false) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    }
+    0
+}
+
+/// Headless mode: serve GET /metrics (Prometheus text format) until interrupted
+/// Each scrape re-parses the logs, so requests are handled one at a time
+/// An idle client is dropped after a read timeout instead of blocking the next scrape
+fn run_metrics_server(port: u16, plan_index: usize) -> Result<(), String> {
+    let bind = load_config()?.metrics_bind;
+    let address = SocketAddr::new(bind, port);
+    let listener = TcpListener::bind(address).map_err(|e| format!("Cannot listen on {}: {}", address, e))?;
+    eprintln!("Serving metrics on http://{}/metrics", address);
+
+    let timeout = Some(Duration::from_secs(METRICS_READ_TIMEOUT_SECS));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        if stream.set_read_timeout(timeout).and(stream.set_write_timeout(timeout)).is_err() {
+            continue;
+        }
+        let mut request_line = String::new();
+        if BufReader::new(&stream).read_line(&mut request_line).is_err() {
+            continue;
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or("");
+        let (status, content_type, body) = if path != "/metrics" {
+            ("404 Not Found", "text/plain", "Not found\n".to_string())
+        } else {
+            match metrics_snapshot(plan_index) {
+                Ok(body) => ("200 OK", METRICS_CONTENT_TYPE, body),
+                Err(e) => ("500 Internal Server Error", "text/plain", format!("{}\n", e)),
+            }
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+    }
+    Ok(())
 }
 
 fn main() {
+    let cli = match parse_args() {
+        Ok(cli) => CLI.get_or_init(|| cli),
+        Err(e) => {
+            attach_parent_console();
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if cli.headless() {
+        attach_parent_console();
+    }
+
+    if cli.stdin {
+        STDIN_ENTRIES.get_or_init(read_stdin_entries);
+    }
+
+    // First interactive run: drop a commented config so the options are discoverable.
+    // Scripted runs (headless modes, piped --stdin) leave the home directory alone.
+    // Before the first load, so the new file isn't later seen as an edit
+    if !cli.headless() && !cli.stdin {
+        match write_default_config() {
+            Ok(Some(path)) => eprintln!("Created default config at {}", path.display()),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    // Fail early on a bad --plan / config plan (config errors are reported by the UI)
+    let initial_plan = match load_config().map(|config| initial_plan_index(&config)) {
+        Ok(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+        Ok(Ok(plan)) => plan,
+        Err(_) => None,
+    };
+
+    // Headless modes skip the window entirely
+    if cli.today {
+        std::process::exit(run_today(initial_plan.unwrap_or(DEFAULT_PLAN_INDEX), cli.json));
+    }
+    if let Some(target) = &cli.export_blocks {
+        if let Err(e) = run_export_blocks(target) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if cli.json {
+        run_json_mode(initial_plan.unwrap_or(DEFAULT_PLAN_INDEX));
+        return;
+    }
+
+    if let Some(port) = cli.metrics_port {
+        if let Err(e) = run_metrics_server(port, initial_plan.unwrap_or(DEFAULT_PLAN_INDEX)) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
-        .invoke_handler(tauri::generate_handler![get_dashboard_data, get_available_plans])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(Mutex::new(ThresholdTracker::default()))
+        .manage(start_log_watcher())
+        .manage(ActivePlan(AtomicUsize::new(initial_plan.unwrap_or(DEFAULT_PLAN_INDEX))))
+        .invoke_handler(tauri::generate_handler![
+            get_dashboard_data,
+            get_block_prediction,
+            get_available_plans,
+            get_initial_plan,
+            save_selected_plan,
+            adjust_custom_plan,
+            get_inferred_plan,
+            get_usage_heatmap,
+            get_daily_costs,
+            get_blocks_csv,
+            take_logs_changed
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(move |app, event| {
+            // Lands in the terminal's scrollback when launched from a shell
+            if let tauri::RunEvent::Exit = event {
+                if !cli.quiet {
+                    // The plan shown when the window closed, not the one at startup
+                    let plan_index = app.state::<ActivePlan>().get();
+                    match exit_summary(plan_index) {
+                        Ok(summary) => println!("{}", summary),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use claude_dashboard_lib::config::Zone;
+
+    #[test]
+    fn block_prediction_matches_the_dashboard_current_block() {
+        let now = Utc::now();
+        let jsonl: String = [26 * 60, 20 * 60, 90, 45, 5]
+            .iter()
+            .map(|minutes| {
+                format!(
+                    "{{\"timestamp\":\"{}\",\"sessionId\":\"s1\",\"message\":{{\"model\":\"claude-sonnet-4\",\"usage\":{{\"input_tokens\":100,\"output_tokens\":1000}}}}}}\n",
+                    (now - ChronoDuration::minutes(*minutes)).to_rfc3339()
+                )
+            })
+            .collect();
+        let entries = parse_reader(jsonl.as_bytes());
+        let config = Config::default();
+
+        let full = create_blocks(&entries, &config.blocks, &config.limit_policy);
+        assert_eq!(full.len(), 3);
+        for plan_index in [0, 1, 99] {
+            let (_, _, dashboard) = current_block_for(&full, &config, plan_index);
+            let (_, _, prediction) = current_block_for(&latest_block(&entries, &config), &config, plan_index);
+            assert!(dashboard.block_start.is_some());
+            assert_eq!(prediction.block_start, dashboard.block_start);
+            assert_eq!(prediction.limit_messages, dashboard.limit_messages);
+            assert_eq!(prediction.limit_tokens, dashboard.limit_tokens);
+            assert_eq!(prediction.cost_percent, dashboard.cost_percent);
+        }
+    }
+
+    #[test]
+    fn exit_summary_reports_today_and_the_block_reset() {
+        let config = Config { timezone: Zone::Named(chrono_tz::UTC), ..Default::default() };
+        let today = PeriodStats { limit_cost: 4.2, total_calls: 37, ..Default::default() };
+        let reset = "2025-03-01T16:00:00Z".parse().unwrap();
+        let mut block = CurrentBlockInfo { reset_time: Some(reset), is_active: true, ..Default::default() };
+        assert_eq!(format_exit_summary(&today, &block, &config), "Today: $4.20 across 37 calls, block resets at 16:00");
+
+        block.is_active = false;
+        assert_eq!(format_exit_summary(&today, &block, &config), "Today: $4.20 across 37 calls, no active block");
+    }
+
+    #[test]
+    fn today_json_has_the_status_bar_fields() {
+        let jsonl = r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"s1","message":{"model":"claude-sonnet-4-20250514","usage":{"output_tokens":1000}}}
+{"timestamp":"2025-03-01T10:30:00Z","sessionId":"s1","message":{"model":"claude-sonnet-4-20250514","usage":{"output_tokens":1000}}}"#;
+        let entries = parse_reader(jsonl.as_bytes());
+        let today = aggregate(&entries, "Today", &Default::default());
+        let reset = "2025-03-01T15:00:00Z".parse().unwrap();
+        let block = CurrentBlockInfo { reset_time: Some(reset), is_active: true, cost_percent: 12.5, tokens_percent: 40.0, ..Default::default() };
+
+        let json = today_json(&today, &block);
+        assert_eq!(json["tokens"], 2_000);
+        assert_eq!(json["calls"], 2);
+        assert_eq!(json["cost"], today.limit_cost);
+        assert_eq!(json["block_reset"], "2025-03-01T15:00:00Z");
+        assert_eq!(json["percent"], 40.0);
+
+        let idle = CurrentBlockInfo { is_active: false, ..block };
+        assert!(today_json(&today, &idle)["block_reset"].is_null());
+    }
 }
@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::models::{CurrentBlockInfo, ModelStats, PeriodStats};
+
+/// Content type of the Prometheus text exposition format
+pub const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Per-model metric: name, help text, value
+type ModelMetric = (&'static str, &'static str, fn(&ModelStats) -> f64);
+
+/// Metrics exported once per model (today's usage)
+const MODEL_METRICS: [ModelMetric; 3] = [
+    ("claude_model_cost", "Cost per model today in dollars", |m| m.cost),
+    ("claude_model_tokens", "Tokens per model today (all categories)", |m| m.total_tokens() as f64),
+    ("claude_model_calls", "Calls per model today", |m| m.call_count as f64),
+];
+
+/// Escape a label value (backslash, quote and newline)
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Write one unlabelled gauge with its HELP/TYPE header
+fn gauge(out: &mut String, name: &str, help: &str, value: f64) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Render the current block and today's per-model usage in Prometheus text format
+pub fn render_metrics(block: &CurrentBlockInfo, today: &PeriodStats) -> String {
+    let mut out = String::new();
+    gauge(&mut out, "claude_block_active", "1 while a 5h block is active", f64::from(u8::from(block.is_active)));
+    gauge(&mut out, "claude_block_cost", "Limit cost of the current block in dollars", block.limit_cost);
+    gauge(&mut out, "claude_block_tokens", "Limit tokens of the current block", block.limit_tokens as f64);
+    gauge(&mut out, "claude_cost_percent", "Current block cost as a percent of the plan limit", block.cost_percent);
+    gauge(&mut out, "claude_tokens_percent", "Current block tokens as a percent of the plan limit", block.tokens_percent);
+    gauge(&mut out, "claude_secs_until_reset", "Seconds until the current block resets", block.secs_until_reset as f64);
+
+    for (name, help, value) in MODEL_METRICS {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        for m in &today.models {
+            let _ = writeln!(
+                out,
+                "{}{{model=\"{}\",tier=\"{}\"}} {}",
+                name,
+                escape_label(&m.model),
+                escape_label(&m.tier),
+                value(m)
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_use_the_prometheus_text_format() {
+        let block = CurrentBlockInfo {
+            is_active: true,
+            limit_cost: 12.5,
+            limit_tokens: 40_000,
+            cost_percent: 35.7,
+            secs_until_reset: 3_600,
+            ..Default::default()
+        };
+        let model = ModelStats { model: "custom \"x\"".into(), tier: "Unknown".into(), cost: 1.25, call_count: 3, output_tokens: 10, ..Default::default() };
+        let today = PeriodStats { models: vec![model], ..Default::default() };
+        let text = render_metrics(&block, &today);
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[..3], ["# HELP claude_block_active 1 while a 5h block is active", "# TYPE claude_block_active gauge", "claude_block_active 1"]);
+        for sample in ["claude_block_cost 12.5", "claude_block_tokens 40000", "claude_cost_percent 35.7", "claude_secs_until_reset 3600"] {
+            assert!(lines.contains(&sample), "missing {sample}");
+        }
+        assert!(lines.contains(&r#"claude_model_cost{model="custom \"x\"",tier="Unknown"} 1.25"#));
+        assert!(lines.contains(&r#"claude_model_calls{model="custom \"x\"",tier="Unknown"} 3"#));
+        // Every sample ends with a numeric value
+        assert!(lines.iter().filter(|l| !l.starts_with('#')).all(|l| l.rsplit_once(' ').unwrap().1.parse::<f64>().is_ok()));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("# TYPE")).count(), 9);
+    }
+}
@@ -1,21 +1,88 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::alerts::WarningThresholds;
+use crate::calculator::{
+    cost_per_output_mtok, get_pricing, get_tier, is_system_model, warn_unknown_model, CreditSettings, CurrencySettings,
+    TokenCosts, TokenFormat, SYSTEM_MODEL,
+};
+
+/// Session id of entries logged without one (replaced by the file stem when parsed from disk)
+pub const UNKNOWN_SESSION: &str = "unknown";
 
 /// Raw usage data from JSONL
+/// Supported shapes: `message.{model, usage}` (Claude Code), top-level `model`/`usage`
+/// (tool and streaming events), and `usage` as an array of parts that are summed
 #[derive(Debug, Deserialize)]
 pub struct RawEntry {
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub timestamp: DateTime<Utc>,
     #[serde(rename = "sessionId")]
     pub session_id: Option<String>,
     pub message: Option<Message>,
+    /// Model logged next to `message` instead of inside it
+    pub model: Option<String>,
+    /// Usage logged next to `message` instead of inside it
+    #[serde(default, deserialize_with = "deserialize_usage")]
+    pub usage: Option<Usage>,
+    /// Precomputed cost logged by newer Claude Code versions
+    #[serde(rename = "costUSD")]
+    pub cost_usd: Option<f64>,
+}
+
+/// Epoch values at or above this are milliseconds (1e11 s is beyond year 5000)
+const EPOCH_MILLIS_THRESHOLD: f64 = 1e11;
+
+/// Accept an RFC 3339 string or a numeric epoch in seconds or milliseconds (older logs)
+pub(crate) fn deserialize_timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawTimestamp {
+        Text(String),
+        Epoch(f64),
+    }
+
+    let parsed = match RawTimestamp::deserialize(deserializer)? {
+        RawTimestamp::Text(text) => DateTime::parse_from_rfc3339(&text).ok().map(|ts| ts.with_timezone(&Utc)),
+        RawTimestamp::Epoch(value) => {
+            let millis = if value.abs() >= EPOCH_MILLIS_THRESHOLD { value } else { value * 1000.0 };
+            Utc.timestamp_millis_opt(millis as i64).single()
+        }
+    };
+    parsed.ok_or_else(|| serde::de::Error::custom("invalid timestamp"))
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Message {
     pub model: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_usage")]
     pub usage: Option<Usage>,
 }
 
+/// Accept a usage object or an array of usage parts (summed)
+fn deserialize_usage<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Usage>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawUsage {
+        Parts(Vec<Usage>),
+        Single(Usage),
+    }
+
+    Ok(match Option::<RawUsage>::deserialize(deserializer)? {
+        None => None,
+        Some(RawUsage::Single(usage)) => Some(usage),
+        Some(RawUsage::Parts(parts)) => Some(parts.iter().fold(Usage::default(), |mut sum, part| {
+            sum.input_tokens = sum.input_tokens.saturating_add(part.input_tokens);
+            sum.output_tokens = sum.output_tokens.saturating_add(part.output_tokens);
+            sum.cache_creation_input_tokens = sum.cache_creation_input_tokens.saturating_add(part.cache_creation_input_tokens);
+            sum.cache_read_input_tokens = sum.cache_read_input_tokens.saturating_add(part.cache_read_input_tokens);
+            sum
+        })),
+    })
+}
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct Usage {
     #[serde(default)]
@@ -29,9 +96,40 @@ pub struct Usage {
 }
 
 impl Usage {
+    /// All categories summed; saturates instead of overflowing on corrupt counts
     pub fn total(&self) -> u64 {
-        self.input_tokens + self.output_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+        self.input_tokens
+            .saturating_add(self.output_tokens)
+            .saturating_add(self.cache_creation_input_tokens)
+            .saturating_add(self.cache_read_input_tokens)
     }
+
+    /// Cap every category at `max`; true when anything was clamped
+    pub fn clamp_to(&mut self, max: u64) -> bool {
+        let mut clamped = false;
+        for tokens in [
+            &mut self.input_tokens,
+            &mut self.output_tokens,
+            &mut self.cache_creation_input_tokens,
+            &mut self.cache_read_input_tokens,
+        ] {
+            if *tokens > max {
+                *tokens = max;
+                clamped = true;
+            }
+        }
+        clamped
+    }
+}
+
+/// Where an entry was read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntrySource {
+    /// A Claude Code log line (one API call)
+    #[default]
+    Log,
+    /// A day total from an Anthropic console usage export
+    ConsoleImport,
 }
 
 /// Parsed entry with all required fields
@@ -41,26 +139,41 @@ pub struct Entry {
     pub session_id: String,
     pub model: String,
     pub usage: Usage,
+    /// Project key derived from the JSONL path (filled in by parse_all)
+    pub project: String,
+    /// Logged cost, preferred over the pricing table when present
+    pub cost_usd: Option<f64>,
+    /// Pricing-table cost per token category (computed once at parse time)
+    pub costs: TokenCosts,
+    pub source: EntrySource,
 }
 
 impl TryFrom<RawEntry> for Entry {
     type Error = ();
 
     fn try_from(raw: RawEntry) -> Result<Self, Self::Error> {
-        let message = raw.message.ok_or(())?;
-        let usage = message.usage.ok_or(())?;
-        let model = message.model.ok_or(())?;
+        // message.* wins; the top-level fields fill in what it lacks
+        let (message_model, message_usage) = raw.message.map_or((None, None), |m| (m.model, m.usage));
+        let usage = message_usage.or(raw.usage).ok_or(())?;
+        let model = message_model.or(raw.model).ok_or(())?;
+        let system = is_system_model(&model);
 
         // Skip entries with no tokens
         if usage.total() == 0 {
             return Err(());
         }
 
+        let model = if system { SYSTEM_MODEL.into() } else { model };
         Ok(Entry {
             timestamp: raw.timestamp,
-            session_id: raw.session_id.unwrap_or_else(|| "unknown".into()),
+            session_id: raw.session_id.unwrap_or_else(|| UNKNOWN_SESSION.into()),
+            costs: TokenCosts::new(&model, &usage),
             model,
             usage,
+            project: String::new(),
+            // System entries stay free even if the log carries a cost
+            cost_usd: if system { None } else { raw.cost_usd },
+            source: EntrySource::Log,
         })
     }
 }
@@ -69,28 +182,69 @@ impl TryFrom<RawEntry> for Entry {
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct ModelStats {
     pub model: String,
+    /// Model family ("Unknown" = priced as Sonnet, an estimate)
+    pub tier: String,
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_create_tokens: u64,
     pub cache_read_tokens: u64,
     pub call_count: u64,
+    /// Summed per-entry cost (logged or computed)
+    pub cost: f64,
+    /// cache_read share of cache tokens, in percent (filled by aggregate)
+    pub cache_hit_percent: f64,
+    /// Dollars saved by cache reads vs paying full input price (filled by aggregate)
+    pub cache_savings: f64,
+    /// Effective price per 1M output tokens (filled by aggregate)
+    pub cost_per_output_mtok: f64,
 }
 
 impl ModelStats {
     pub fn new(model: String) -> Self {
-        Self { model, ..Default::default() }
+        warn_unknown_model(&model);
+        Self {
+            tier: get_tier(&model).to_string(),
+            model,
+            ..Default::default()
+        }
     }
 
-    pub fn add(&mut self, usage: &Usage) {
-        self.input_tokens += usage.input_tokens;
-        self.output_tokens += usage.output_tokens;
-        self.cache_create_tokens += usage.cache_creation_input_tokens;
-        self.cache_read_tokens += usage.cache_read_input_tokens;
+    pub fn add(&mut self, usage: &Usage, cost: f64) {
+        self.input_tokens = self.input_tokens.saturating_add(usage.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(usage.output_tokens);
+        self.cache_create_tokens = self.cache_create_tokens.saturating_add(usage.cache_creation_input_tokens);
+        self.cache_read_tokens = self.cache_read_tokens.saturating_add(usage.cache_read_input_tokens);
         self.call_count += 1;
+        self.cost += cost;
     }
 
     pub fn total_tokens(&self) -> u64 {
-        self.input_tokens + self.output_tokens + self.cache_create_tokens + self.cache_read_tokens
+        self.input_tokens
+            .saturating_add(self.output_tokens)
+            .saturating_add(self.cache_create_tokens)
+            .saturating_add(self.cache_read_tokens)
+    }
+
+    /// cache_read / (cache_read + cache_create), 0.0 without cache tokens
+    pub fn cache_hit_ratio(&self) -> f64 {
+        let cache_total = self.cache_read_tokens.saturating_add(self.cache_create_tokens);
+        if cache_total > 0 {
+            self.cache_read_tokens as f64 / cache_total as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Total cost per 1M output tokens, 0.0 without output
+    /// Input and cache spend push this above the model's list output price
+    pub fn cost_per_output_mtok(&self) -> f64 {
+        cost_per_output_mtok(self.cost, self.output_tokens)
+    }
+
+    /// Cache reads billed at the read price instead of the full input price
+    pub fn estimated_cache_savings(&self) -> f64 {
+        let pricing = get_pricing(&self.model);
+        (self.cache_read_tokens as f64 / 1_000_000.0) * (pricing.input - pricing.cache_read)
     }
 }
 
@@ -101,27 +255,102 @@ pub struct PeriodStats {
     pub total_tokens: u64,
     pub total_cost: f64,
     pub total_calls: u64,
+    /// Cost towards limit (the categories counted by the limit policy)
+    pub limit_cost: f64,
+    /// Tokens towards limit (categories set by LimitPolicy)
+    pub limit_tokens: u64,
     pub session_count: usize,
     pub period_label: String,
+    /// Earliest entry in the period (None when empty)
+    pub first_timestamp: Option<DateTime<Utc>>,
+    /// Latest entry in the period (None when empty)
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// Effective price per 1M output tokens across all models
+    pub cost_per_output_mtok: f64,
+    /// Average cost per session (filled by aggregate)
+    pub avg_cost_per_session: f64,
+    /// Average tokens per session (filled by aggregate)
+    pub avg_tokens_per_session: f64,
+}
+
+impl PeriodStats {
+    /// Total cost divided by session count, 0.0 without sessions
+    pub fn avg_cost_per_session(&self) -> f64 {
+        if self.session_count > 0 {
+            self.total_cost / self.session_count as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Total tokens divided by session count, 0.0 without sessions
+    pub fn avg_tokens_per_session(&self) -> f64 {
+        if self.session_count > 0 {
+            self.total_tokens as f64 / self.session_count as f64
+        } else {
+            0.0
+        }
+    }
 }
 
 /// Plan limits (from claude-monitor/core/plans.py)
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PlanLimits {
+    #[serde(default = "custom_plan_name")]
     pub name: String,
     pub token_limit: u64,
     pub cost_limit: f64,
     pub message_limit: u64,
+    /// Per-tier block cost caps on top of cost_limit (e.g. Opus = 50.0); empty = none
+    #[serde(default)]
+    pub tier_cost_limits: BTreeMap<String, f64>,
+    /// Cost cap over a rolling 7 days (0 = none)
+    #[serde(default)]
+    pub weekly_cost_limit: f64,
+    /// Token cap over a rolling 7 days (0 = none)
+    #[serde(default)]
+    pub weekly_token_limit: u64,
+}
+
+impl PlanLimits {
+    /// Block limits scaled by `factor` (rounded, cost to the cent)
+    /// 0 = unlimited stays 0, and a set limit never rounds down to unlimited
+    pub fn scaled(&self, factor: f64) -> Self {
+        let count = |limit: u64| if limit == 0 { 0 } else { ((limit as f64 * factor).round() as u64).max(1) };
+        let cost = if self.cost_limit > 0.0 {
+            ((self.cost_limit * factor * 100.0).round() / 100.0).max(0.01)
+        } else {
+            0.0
+        };
+        Self {
+            token_limit: count(self.token_limit),
+            cost_limit: cost,
+            message_limit: count(self.message_limit),
+            ..self.clone()
+        }
+    }
 }
 
 pub fn get_plans() -> Vec<PlanLimits> {
     vec![
-        PlanLimits { name: "Pro".into(), token_limit: 19_000, cost_limit: 18.0, message_limit: 250 },
-        PlanLimits { name: "Max5".into(), token_limit: 88_000, cost_limit: 35.0, message_limit: 1_000 },
-        PlanLimits { name: "Max20".into(), token_limit: 220_000, cost_limit: 140.0, message_limit: 2_000 },
+        PlanLimits { name: "Pro".into(), token_limit: 19_000, cost_limit: 18.0, message_limit: 250, ..Default::default() },
+        PlanLimits { name: "Max5".into(), token_limit: 88_000, cost_limit: 35.0, message_limit: 1_000, ..Default::default() },
+        PlanLimits { name: "Max20".into(), token_limit: 220_000, cost_limit: 140.0, message_limit: 2_000, ..Default::default() },
     ]
 }
 
+/// Find a plan by index ("2") or case-insensitive name ("max20")
+pub fn find_plan(plans: &[PlanLimits], query: &str) -> Option<usize> {
+    if let Ok(index) = query.parse::<usize>() {
+        return (index < plans.len()).then_some(index);
+    }
+    plans.iter().position(|p| p.name.eq_ignore_ascii_case(query))
+}
+
+fn custom_plan_name() -> String {
+    "Custom".into()
+}
+
 pub static PLANS: std::sync::LazyLock<Vec<PlanLimits>> = std::sync::LazyLock::new(get_plans);
 
 /// A 5-hour session block (like claude-monitor)
@@ -139,6 +368,49 @@ pub struct SessionBlock {
     pub stats: PeriodStats,
 }
 
+/// Why the dashboard has no data yet
+#[derive(Debug, Clone, Serialize)]
+pub struct EmptyState {
+    /// Directory where Claude Code logs are expected
+    pub data_dir: String,
+    /// The directory does not exist (Claude Code never ran here)
+    pub missing: bool,
+}
+
+/// Past or current block summary for the history view
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockSummary {
+    pub start_time: DateTime<Utc>,
+    /// Block end time (= reset time)
+    pub end_time: DateTime<Utc>,
+    pub is_active: bool,
+    /// Cost towards limit
+    pub limit_cost: f64,
+    /// Tokens towards limit
+    pub limit_tokens: u64,
+    pub messages: u64,
+}
+
+/// Projected exhaustion compared to the block reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(tag = "kind")]
+pub enum ResetVerdict {
+    /// A limit runs out this many seconds before the reset
+    WillExhaust { before_secs: i64 },
+    /// Reset comes first (or nothing is burning)
+    #[default]
+    WillSurvive,
+}
+
+/// One tier's block cost against its own cap
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TierLimitUsage {
+    pub tier: String,
+    pub limit_cost: f64,
+    pub cost_limit: f64,
+    pub percent: f64,
+}
+
 /// Current block info for display
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct CurrentBlockInfo {
@@ -150,9 +422,9 @@ pub struct CurrentBlockInfo {
     pub secs_until_reset: i64,
 
     // === LIMIT METRICS (what counts towards rate limit) ===
-    /// Cost towards limit (input + output only)
+    /// Cost towards limit (the categories counted by the limit policy)
     pub limit_cost: f64,
-    /// Tokens towards limit (input + output only)
+    /// Tokens towards limit (categories set by LimitPolicy)
     pub limit_tokens: u64,
     /// Messages count
     pub limit_messages: u64,
@@ -167,6 +439,8 @@ pub struct CurrentBlockInfo {
     pub cost_percent: f64,
     pub tokens_percent: f64,
     pub messages_percent: f64,
+    /// Usage against the plan's per-tier cost caps (empty when none are set)
+    pub tier_limits: Vec<TierLimitUsage>,
 
     // === BURN RATE ===
     /// Tokens per minute
@@ -181,9 +455,94 @@ pub struct CurrentBlockInfo {
     pub tokens_exhausted_at: Option<DateTime<Utc>>,
     /// Predicted time when cost limit hit (timestamp)
     pub cost_exhausted_at: Option<DateTime<Utc>>,
+    /// Whether the predicted exhaustion comes before the reset
+    pub verdict: ResetVerdict,
+
+    // === LIVE CALL ===
+    /// Newest entry landed within the live window (call just happened)
+    pub live_call: bool,
+    /// Limit cost of that newest entry
+    pub live_call_cost: f64,
+
+    /// Limit tokens per elapsed hour slot of the block (oldest first)
+    pub hourly_tokens: Vec<u64>,
 
     /// Is currently active (within 5h window)?
     pub is_active: bool,
+    /// End of the most recent block when none is active (limits fully reset)
+    pub last_block_end: Option<DateTime<Utc>>,
+}
+
+/// Weekly window info for display (like CurrentBlockInfo, over a week)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WeeklyBlockInfo {
+    /// Last weekly reset
+    pub week_start: Option<DateTime<Utc>>,
+    /// Next weekly reset
+    pub reset_time: Option<DateTime<Utc>>,
+    /// Seconds until the weekly reset
+    pub secs_until_reset: i64,
+    /// Cost towards limit this week
+    pub limit_cost: f64,
+    /// Tokens towards limit this week
+    pub limit_tokens: u64,
+    /// Messages this week
+    pub limit_messages: u64,
+    /// Cost cap of the window ([week_reset] cost_limit, 0 = none)
+    pub cost_limit: f64,
+    pub cost_percent: f64,
+}
+
+/// Usage over the last 7 days against the plan's weekly caps
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WeeklyLimitInfo {
+    /// Plan has a weekly cost or token cap (otherwise the panel is hidden)
+    pub enabled: bool,
+    pub limit_cost: f64,
+    pub limit_tokens: u64,
+    pub cost_limit: f64,
+    pub token_limit: u64,
+    pub cost_percent: f64,
+    pub tokens_percent: f64,
+    /// When the oldest usage in the window rolls out (first relief), None when empty
+    pub reset_estimate: Option<DateTime<Utc>>,
+}
+
+/// Downgrades to a lower model tier within sessions (e.g. Opus limit hit, Sonnet fallback)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TierFallbacks {
+    pub fallback_count: usize,
+    /// Time of the first lower-tier call of each downgrade, oldest first
+    pub timestamps: Vec<DateTime<Utc>>,
+}
+
+/// Cost per model tier for a period
+#[derive(Debug, Clone, Serialize)]
+pub struct TierCost {
+    pub tier: String,
+    pub cost: f64,
+    pub calls: u64,
+}
+
+/// Period comparison (is usage accelerating?)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Trends {
+    /// Today's cost as a percent of this week's
+    pub today_of_week_percent: f64,
+    /// This week's cost as a percent of this month's
+    pub week_of_month_percent: f64,
+    /// Average daily cost this week (days elapsed so far)
+    pub week_daily_avg: f64,
+    /// Average daily cost this month (days elapsed so far)
+    pub month_daily_avg: f64,
+    /// Today's cost is above the month's daily average
+    pub accelerating: bool,
+    /// Today's cost extrapolated to the end of the day
+    pub projected_end_of_day: f64,
+    /// This month's cost extrapolated to the end of the month
+    pub projected_end_of_month: f64,
+    /// This month's billed cost extrapolated to the end of the month
+    pub projected_month_bill: f64,
 }
 
 /// Model distribution info
@@ -194,7 +553,10 @@ pub struct ModelDistribution {
     pub calls: u64,
     pub tokens: u64,
     pub cost: f64,
+    /// Share of the block's limit cost
     pub percent: f64,
+    /// Share of the selected plan's cost limit (sums to the block's cost_percent)
+    pub limit_percent: f64,
 }
 
 /// Dashboard data sent to frontend
@@ -204,9 +566,177 @@ pub struct DashboardData {
     pub today: PeriodStats,
     pub week: PeriodStats,
     pub month: PeriodStats,
+    pub all_time: PeriodStats,
+    /// Previous day, for the comparison view
+    pub yesterday: PeriodStats,
+    /// Previous week (same kind as `week`), for the comparison view
+    pub last_week: PeriodStats,
     pub selected_plan: PlanLimits,
+    /// Plan originally selected, when usage forced an automatic switch up
+    pub auto_switched_from: Option<String>,
     /// Model distribution in current block
     pub model_distribution: Vec<ModelDistribution>,
+    /// Current block per model version instead of per tier
+    pub model_version_distribution: Vec<ModelDistribution>,
+    /// Most recent blocks, newest first (capped by config)
+    pub block_history: Vec<BlockSummary>,
+    /// Limit cost in cents of the last blocks, oldest first (sparkline)
+    pub block_costs: Vec<u64>,
+    /// Start times of completed blocks this month that hit the maxed-out threshold
+    pub maxed_blocks: Vec<DateTime<Utc>>,
+    /// Usage since the last weekly reset
+    pub weekly: WeeklyBlockInfo,
+    /// Rolling 7-day usage against the plan's weekly caps
+    pub weekly_limits: WeeklyLimitInfo,
+    /// Per-project stats for this month (project key, stats)
+    pub projects: Vec<(String, PeriodStats)>,
+    /// Cost per tier this month (sums to month.total_cost)
+    pub tier_breakdown: Vec<TierCost>,
+    /// Tier downgrades within sessions this month
+    pub tier_fallbacks: TierFallbacks,
+    /// Limit cost per local weekday this month (0 = Mon)
+    pub weekday_costs: [f64; 7],
+    /// Most expensive sessions this month (session id, stats)
+    pub sessions: Vec<(String, PeriodStats)>,
+    /// Today vs week vs month comparison
+    pub trends: Trends,
+    /// Most expensive single call today (timestamp, model, cost)
+    pub costliest_call_today: Option<(DateTime<Utc>, String, f64)>,
+    /// Most expensive single call this month (timestamp, model, cost)
+    pub costliest_call_month: Option<(DateTime<Utc>, String, f64)>,
+    /// Configured IANA timezone (None = system local)
+    pub timezone: Option<String>,
+    /// Show ASCII markers instead of emoji
+    pub ascii: bool,
+    /// Credit display settings when enabled (costs stay in USD)
+    pub credits: Option<CreditSettings>,
+    /// Display currency (costs stay in USD)
+    pub currency: CurrencySettings,
+    /// Token count display format
+    pub token_format: TokenFormat,
+    /// Warning thresholds, including the optional soft limits used for gauge colors
+    pub thresholds: WarningThresholds,
+    /// Set when no usage was found, to explain where logs are expected
+    pub empty_state: Option<EmptyState>,
+    /// Log files that could not be read this refresh
+    pub skipped_files: usize,
+    /// Data is a fixed snapshot (--stdin), refreshing won't change it
+    pub snapshot: bool,
+    /// When an edited config file was last picked up (None = unchanged since startup)
+    pub config_reloaded_at: Option<DateTime<Utc>>,
+    /// Why the edited config was rejected (the previous one stays in use)
+    pub config_error: Option<String>,
     /// Warning flags
     pub warnings: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaling_a_plan_keeps_unlimited_limits() {
+        let plan = PlanLimits { name: "Team".into(), token_limit: 100_000, cost_limit: 50.0, message_limit: 0, ..Default::default() };
+        let up = plan.scaled(1.1);
+        assert_eq!((up.token_limit, up.cost_limit, up.message_limit), (110_000, 55.0, 0));
+        assert_eq!(up.name, "Team");
+
+        let down = plan.scaled(0.9);
+        assert_eq!((down.token_limit, down.cost_limit, down.message_limit), (90_000, 45.0, 0));
+        assert_eq!(PlanLimits::default().scaled(1.1).cost_limit, 0.0);
+    }
+
+    #[test]
+    fn scaling_down_never_reaches_unlimited() {
+        let tiny = PlanLimits { token_limit: 1, cost_limit: 0.01, message_limit: 1, ..Default::default() };
+        let down = tiny.scaled(0.9);
+        assert_eq!((down.token_limit, down.cost_limit, down.message_limit), (1, 0.01, 1));
+    }
+
+    #[test]
+    fn cache_hit_ratio_and_savings_worked_example() {
+        // 3M reads, 1M writes at Sonnet prices: reads cost $0.30/M instead of $3/M
+        let stats = ModelStats {
+            model: "claude-sonnet-4".into(),
+            cache_read_tokens: 3_000_000,
+            cache_create_tokens: 1_000_000,
+            ..Default::default()
+        };
+        assert_eq!(stats.cache_hit_ratio(), 0.75);
+        assert!((stats.estimated_cache_savings() - 8.1).abs() < 1e-9);
+
+        let no_cache = ModelStats { model: "claude-sonnet-4".into(), output_tokens: 10, ..Default::default() };
+        assert_eq!(no_cache.cache_hit_ratio(), 0.0);
+        assert_eq!(no_cache.estimated_cache_savings(), 0.0);
+    }
+
+    #[test]
+    fn plans_are_found_by_name_or_index() {
+        let plans = get_plans();
+        assert_eq!(find_plan(&plans, "max20"), Some(2));
+        assert_eq!(find_plan(&plans, "Pro"), Some(0));
+        assert_eq!(find_plan(&plans, "1"), Some(1));
+        assert_eq!(find_plan(&plans, "3"), None);
+        assert_eq!(find_plan(&plans, "enterprise"), None);
+    }
+
+    fn parse(line: &str) -> Option<Entry> {
+        serde_json::from_str::<RawEntry>(line).ok().and_then(|raw| Entry::try_from(raw).ok())
+    }
+
+    #[test]
+    fn epoch_and_rfc3339_timestamps_give_the_same_entry() {
+        let usage = r#""message":{"model":"claude-sonnet-4","usage":{"output_tokens":5}}"#;
+        let rfc = parse(&format!(r#"{{"timestamp":"2025-03-01T10:00:00.250Z",{usage}}}"#)).unwrap();
+        let millis = parse(&format!(r#"{{"timestamp":1740823200250,{usage}}}"#)).unwrap();
+        let secs = parse(&format!(r#"{{"timestamp":1740823200.25,{usage}}}"#)).unwrap();
+
+        assert_eq!(rfc.timestamp, millis.timestamp);
+        assert_eq!(rfc.timestamp, secs.timestamp);
+        assert_eq!(rfc.model, millis.model);
+        assert_eq!(rfc.usage.output_tokens, millis.usage.output_tokens);
+    }
+
+    #[test]
+    fn bad_or_missing_timestamps_skip_the_line() {
+        let usage = r#""message":{"model":"claude-sonnet-4","usage":{"output_tokens":5}}"#;
+        assert!(parse(&format!(r#"{{"timestamp":"yesterday",{usage}}}"#)).is_none());
+        assert!(parse(&format!("{{{usage}}}")).is_none());
+    }
+
+    #[test]
+    fn session_averages_guard_zero_sessions() {
+        let stats = PeriodStats { total_cost: 12.0, total_tokens: 90_000, session_count: 3, ..Default::default() };
+        assert_eq!(stats.avg_cost_per_session(), 4.0);
+        assert_eq!(stats.avg_tokens_per_session(), 30_000.0);
+
+        let empty = PeriodStats::default();
+        assert_eq!(empty.avg_cost_per_session(), 0.0);
+        assert_eq!(empty.avg_tokens_per_session(), 0.0);
+    }
+
+    #[test]
+    fn top_level_model_and_usage_parts_are_accepted() {
+        let nested = parse(
+            r#"{"timestamp":"2025-03-01T10:00:00Z","message":{"model":"claude-opus-4","usage":{"input_tokens":3,"output_tokens":10}}}"#,
+        )
+        .unwrap();
+        let entry = parse(
+            r#"{"timestamp":"2025-03-01T10:00:00Z","model":"claude-opus-4","usage":[{"input_tokens":3,"output_tokens":4},{"output_tokens":6}]}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.model, nested.model);
+        assert_eq!(entry.usage.input_tokens, nested.usage.input_tokens);
+        assert_eq!(entry.usage.output_tokens, nested.usage.output_tokens);
+        assert_eq!(entry.session_id, UNKNOWN_SESSION);
+    }
+
+    #[test]
+    fn usage_sums_saturate() {
+        let mut usage = Usage { input_tokens: u64::MAX, output_tokens: 1, ..Default::default() };
+        assert_eq!(usage.total(), u64::MAX);
+        assert!(usage.clamp_to(1_000));
+        assert_eq!(usage.total(), 1_001);
+        assert!(!usage.clamp_to(1_000));
+    }
+}
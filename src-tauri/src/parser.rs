@@ -1,20 +1,36 @@
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
-use chrono::{Duration, Local, Timelike, Utc, DateTime};
+use anyhow::{Context, Result};
+use chrono::{Datelike, Duration, NaiveDate, Timelike, Utc, DateTime};
 
-use crate::calculator::{calculate_cost, calculate_entry_cost, calculate_entry_limit_cost, get_limit_tokens, get_tier};
-use crate::models::{CurrentBlockInfo, Entry, ModelDistribution, ModelStats, PeriodStats, PlanLimits, RawEntry, SessionBlock};
+use crate::calculator::{
+    calculate_entry_cost, calculate_entry_limit_cost, cost_per_output_mtok, get_limit_tokens, get_tier, short_model_name,
+    tier_rank, LimitPolicy, TokenCosts, SYSTEM_MODEL,
+};
+use crate::config::{BlockSettings, Config, TierOrder, WeekReset, WorkHours, Zone};
+use crate::import::{is_imported, parse_console_csv};
+use crate::models::{BlockSummary, CurrentBlockInfo, EmptyState, Entry, ModelDistribution, ModelStats, PeriodStats, PlanLimits, ResetVerdict, SessionBlock, TierCost, TierFallbacks, TierLimitUsage, Trends, WeeklyBlockInfo, WeeklyLimitInfo, UNKNOWN_SESSION};
+use crate::schema::map_str;
 
 /// Session duration in hours
 const SESSION_HOURS: i64 = 5;
 
+/// Future timestamps beyond this skew (seconds) are reported as clock problems
+const FUTURE_SKEW_WARN_SECS: i64 = 300;
+
+/// Env var overriding the Claude data directory (used verbatim)
+pub const DATA_DIR_ENV: &str = "CLAUDE_DATA_DIR";
+
 /// Get the Claude data directory
-/// On Windows, tries WSL paths if native path is empty
+/// CLAUDE_DATA_DIR wins if set; on Windows, tries WSL paths if native path is empty
 pub fn get_data_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
     // First try native path
     let native_path = dirs::home_dir().map(|h| h.join(".claude").join("projects"));
 
@@ -72,14 +88,57 @@ fn find_wsl_claude_dir() -> Option<PathBuf> {
     None
 }
 
-/// Find all JSONL files
-pub fn find_jsonl_files(base: &PathBuf) -> Vec<PathBuf> {
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return text == first;
+    }
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Does a scan ignore pattern match this file or directory name?
+/// "name/" matches directories only; patterns with `*` are globs, others substrings
+fn is_ignored(name: &str, is_dir: bool, ignore: &[String]) -> bool {
+    ignore.iter().any(|pattern| {
+        let (pattern, dirs_only) = match pattern.strip_suffix('/') {
+            Some(p) => (p, true),
+            None => (pattern.as_str(), false),
+        };
+        if pattern.is_empty() || (dirs_only && !is_dir) {
+            return false;
+        }
+        if pattern.contains('*') {
+            wildcard_match(pattern, name)
+        } else {
+            name.contains(pattern)
+        }
+    })
+}
+
+/// Find all JSONL files, skipping (and not descending into) ignored names
+pub fn find_jsonl_files(base: &PathBuf, ignore: &[String]) -> Vec<PathBuf> {
     let mut files = Vec::new();
     if let Ok(entries) = std::fs::read_dir(base) {
         for entry in entries.flatten() {
             let path = entry.path();
-            if path.is_dir() {
-                files.extend(find_jsonl_files(&path));
+            let is_dir = path.is_dir();
+            if is_ignored(&entry.file_name().to_string_lossy(), is_dir, ignore) {
+                continue;
+            }
+            if is_dir {
+                files.extend(find_jsonl_files(&path, ignore));
             } else if path.extension().map_or(false, |e| e == "jsonl") {
                 files.push(path);
             }
@@ -88,45 +147,248 @@ pub fn find_jsonl_files(base: &PathBuf) -> Vec<PathBuf> {
     files
 }
 
-/// Parse a single JSONL file
-pub fn parse_file(path: &PathBuf) -> Vec<Entry> {
+/// Parse JSONL lines from any reader, each mapped by the first matching schema
+/// Lines are decoded lossily, so one invalid UTF-8 byte only skips its own line
+pub fn parse_reader<R: BufRead>(mut reader: R) -> Vec<Entry> {
     let mut entries = Vec::new();
+    let mut buf = Vec::new();
 
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return entries,
-    };
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
 
-    let reader = BufReader::new(file);
-    for line in reader.lines().map_while(Result::ok) {
+        let line = String::from_utf8_lossy(&buf);
         if line.trim().is_empty() {
             continue;
         }
-        if let Ok(raw) = serde_json::from_str::<RawEntry>(&line) {
-            if let Ok(entry) = Entry::try_from(raw) {
-                entries.push(entry);
+        entries.extend(map_str(&line));
+    }
+
+    entries
+}
+
+/// Open attempts for a file that looks temporarily locked
+const OPEN_RETRIES: u32 = 3;
+
+/// First retry delay, doubled after each attempt
+const OPEN_BACKOFF_MS: u64 = 50;
+
+/// Errors worth retrying (file locked or mid-rotation)
+/// PermissionDenied is not one: an unreadable file is reported at once instead of
+/// costing the backoff on every refresh
+fn is_transient(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    // 32/33 = Windows sharing/lock violation
+    matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted)
+        || (cfg!(windows) && matches!(e.raw_os_error(), Some(32 | 33)))
+}
+
+/// Parse a single JSONL file
+/// A file that vanished yields no entries; a locked one is retried with backoff
+/// before its error is returned
+pub fn parse_file(path: &PathBuf) -> std::io::Result<Vec<Entry>> {
+    let mut attempt = 0;
+    let mut delay = OPEN_BACKOFF_MS;
+    loop {
+        match File::open(path) {
+            Ok(f) => {
+                let mut entries = parse_reader(BufReader::new(f));
+                fill_missing_sessions(&mut entries, path);
+                return Ok(entries);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) if attempt < OPEN_RETRIES && is_transient(&e) => {
+                std::thread::sleep(std::time::Duration::from_millis(delay));
+                attempt += 1;
+                delay *= 2;
             }
+            Err(e) => return Err(e),
         }
     }
+}
 
-    entries
+/// Derive the project key for a JSONL file from its path under the data dir
+/// Uses the first `project_depth` directories, or the leaf directory if configured
+pub fn project_key(base: &Path, file: &Path, config: &Config) -> String {
+    let dirs: Vec<String> = file
+        .strip_prefix(base)
+        .unwrap_or(file)
+        .parent()
+        .map(|p| p.iter().map(|c| c.to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+
+    if dirs.is_empty() {
+        return "unknown".into();
+    }
+
+    if config.project_use_leaf {
+        return dirs[dirs.len() - 1].clone();
+    }
+
+    let depth = config.project_depth.clamp(1, dirs.len());
+    dirs[..depth].join("/")
+}
+
+/// Use the file stem as session id for entries logged without one
+/// (one JSONL file = one session), so they don't all collapse into "unknown"
+fn fill_missing_sessions(entries: &mut [Entry], path: &Path) {
+    let Some(stem) = path.file_stem().map(|s| s.to_string_lossy()) else {
+        return;
+    };
+    for e in entries.iter_mut().filter(|e| e.session_id == UNKNOWN_SESSION) {
+        e.session_id = stem.to_string();
+    }
+}
+
+/// Parse exactly one JSONL file (single-file mode, no directory scan)
+pub fn parse_single_file(path: &Path) -> Result<Vec<Entry>> {
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    let project = path
+        .file_stem()
+        .map_or_else(|| "unknown".into(), |s| s.to_string_lossy().into_owned());
+
+    let mut entries: Vec<Entry> = parse_reader(BufReader::new(file))
+        .into_iter()
+        .map(|mut e| {
+            e.project = project.clone();
+            e
+        })
+        .collect();
+    fill_missing_sessions(&mut entries, path);
+    entries.sort_by_key(|e| e.timestamp);
+    clamp_future_entries(&mut entries, Utc::now());
+
+    Ok(entries)
+}
+
+/// Entries from every readable log, plus how many files could not be read
+#[derive(Debug, Default)]
+pub struct ParsedLogs {
+    pub entries: Vec<Entry>,
+    /// Files that failed to open (locked or unreadable) this time
+    pub skipped_files: usize,
+    /// Directory scanned for logs (None when `data_file` is set)
+    pub data_dir: Option<PathBuf>,
+    /// The data directory does not exist yet
+    pub data_dir_missing: bool,
+}
+
+impl ParsedLogs {
+    /// First-run guidance when the scanned directory yielded no entries
+    pub fn empty_state(&self) -> Option<EmptyState> {
+        let dir = self.data_dir.as_ref().filter(|_| self.entries.is_empty())?;
+        Some(EmptyState {
+            data_dir: dir.display().to_string(),
+            missing: self.data_dir_missing,
+        })
+    }
 }
 
-/// Parse all JSONL files
-pub fn parse_all() -> Result<Vec<Entry>> {
-    let data_dir = get_data_dir().ok_or_else(|| anyhow::anyhow!("Cannot find home dir"))?;
+/// Remove "(system)" entries (synthetic/empty model names)
+pub fn drop_system_entries(entries: &mut Vec<Entry>) {
+    entries.retain(|e| e.model != SYSTEM_MODEL);
+}
+
+/// Parse all JSONL files (or only `config.data_file` when set)
+pub fn parse_all(config: &Config) -> Result<Vec<Entry>> {
+    parse_all_logs(config).map(|logs| logs.entries)
+}
+
+/// Like parse_all, but also reports files that could not be read
+/// System entries are dropped unless `config.show_system_entries` is set
+pub fn parse_all_logs(config: &Config) -> Result<ParsedLogs> {
+    let mut logs = parse_logs(config)?;
+    if let Some(path) = &config.console_csv {
+        let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+        logs.entries.extend(parse_console_csv(BufReader::new(file), config.timezone));
+        logs.entries.sort_by_key(|e| e.timestamp);
+    }
+    // After the merge, so imported rows are clamped too
+    clamp_future_entries(&mut logs.entries, Utc::now());
+    if !config.show_system_entries {
+        drop_system_entries(&mut logs.entries);
+    }
+    clamp_huge_entries(&mut logs.entries, config.max_entry_tokens);
+    Ok(logs)
+}
+
+fn parse_logs(config: &Config) -> Result<ParsedLogs> {
+    if let Some(path) = &config.data_file {
+        let entries = parse_single_file(path)?;
+        return Ok(ParsedLogs {
+            entries,
+            ..Default::default()
+        });
+    }
+
+    let data_dir = get_data_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot find home dir (set {} to point at your logs)", DATA_DIR_ENV))?;
 
     if !data_dir.exists() {
-        return Ok(Vec::new());
+        return Ok(ParsedLogs {
+            data_dir: Some(data_dir),
+            data_dir_missing: true,
+            ..Default::default()
+        });
     }
 
-    let files = find_jsonl_files(&data_dir);
-    let mut all_entries: Vec<Entry> = files.iter().flat_map(parse_file).collect();
+    let files = find_jsonl_files(&data_dir, &config.scan_ignore);
+    let mut all_entries: Vec<Entry> = Vec::new();
+    let mut skipped_files = 0;
+    for file in &files {
+        let project = project_key(&data_dir, file, config);
+        match parse_file(file) {
+            Ok(entries) => all_entries.extend(entries.into_iter().map(|mut e| {
+                e.project = project.clone();
+                e
+            })),
+            Err(_) => skipped_files += 1,
+        }
+    }
 
     // Sort by timestamp
     all_entries.sort_by_key(|e| e.timestamp);
 
-    Ok(all_entries)
+    Ok(ParsedLogs {
+        entries: all_entries,
+        skipped_files,
+        data_dir: Some(data_dir),
+        data_dir_missing: false,
+    })
+}
+
+/// Cap per-category token counts at `max_tokens` (corrupt lines with absurd counts)
+/// and recompute the costs of the clamped entries; warns when any were clamped
+pub fn clamp_huge_entries(entries: &mut [Entry], max_tokens: u64) {
+    let mut clamped = 0;
+    for entry in entries.iter_mut() {
+        if entry.usage.clamp_to(max_tokens) {
+            entry.costs = TokenCosts::new(&entry.model, &entry.usage);
+            clamped += 1;
+        }
+    }
+
+    if clamped > 0 {
+        eprintln!("{} entries reported over {} tokens in one category; clamped", clamped, max_tokens);
+    }
+}
+
+/// Treat entries stamped after `now` (clock skew) as happening now,
+/// so they still land in the active block; warn when the skew is large
+/// Expects sorted entries and keeps them sorted
+pub fn clamp_future_entries(entries: &mut [Entry], now: DateTime<Utc>) {
+    let mut max_skew = 0;
+    for entry in entries.iter_mut().rev().take_while(|e| e.timestamp > now) {
+        max_skew = max_skew.max((entry.timestamp - now).num_seconds());
+        entry.timestamp = now;
+    }
+
+    if max_skew > FUTURE_SKEW_WARN_SECS {
+        eprintln!("Some entries are {}s in the future (clock skew?); counted as now", max_skew);
+    }
 }
 
 /// Round timestamp to the start of its hour (like claude-monitor)
@@ -140,54 +402,102 @@ fn round_to_hour(ts: DateTime<Utc>) -> DateTime<Utc> {
 }
 
 /// Create session blocks from entries (5-hour blocks like claude-monitor)
-pub fn create_blocks(entries: &[Entry]) -> Vec<SessionBlock> {
-    if entries.is_empty() {
+pub fn create_blocks(entries: &[Entry], settings: &BlockSettings, policy: &LimitPolicy) -> Vec<SessionBlock> {
+    create_recent_blocks(entries, usize::MAX, settings, policy)
+}
+
+/// Create only the `max_blocks` most recent session blocks
+/// Block boundaries are found first, so older blocks are never cloned or aggregated
+pub fn create_recent_blocks(
+    entries: &[Entry],
+    max_blocks: usize,
+    settings: &BlockSettings,
+    policy: &LimitPolicy,
+) -> Vec<SessionBlock> {
+    if entries.is_empty() || max_blocks == 0 {
         return Vec::new();
     }
 
-    let mut blocks: Vec<SessionBlock> = Vec::new();
+    // Console imports are day totals stamped at midnight, not real calls:
+    // they would open phantom blocks, so they only count in period totals.
+    // Callers normally pass sorted entries; re-sort defensively if they don't
+    let sorted;
+    let entries = if !entries.iter().any(is_imported) && entries.windows(2).all(|w| w[0].timestamp <= w[1].timestamp)
+    {
+        entries
+    } else {
+        sorted = {
+            let mut v: Vec<Entry> = entries.iter().filter(|e| !is_imported(e)).cloned().collect();
+            v.sort_by_key(|e| e.timestamp);
+            v
+        };
+        if sorted.is_empty() {
+            return Vec::new();
+        }
+        &sorted[..]
+    };
+
     let session_duration = Duration::hours(SESSION_HOURS);
+    let grace = Duration::seconds(settings.grace_secs.max(0));
 
-    for entry in entries {
+    // (start_time, index of first entry) for each block
+    let mut bounds: Vec<(DateTime<Utc>, usize)> = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
         // Check if we need a new block
-        let need_new_block = match blocks.last() {
+        let need_new_block = match bounds.last() {
             None => true,
-            Some(current) => {
-                // New block if entry is past current block's end time
+            Some((start_time, _)) => {
+                // An entry tied with the previous one always stays in its block
+                // Otherwise new block if entry is past current block's end time (+ grace for late entries)
                 // OR if there's been a 5h+ gap since last entry
-                entry.timestamp >= current.end_time
-                    || (current.entries.last().map_or(true, |last| {
-                        entry.timestamp - last.timestamp >= session_duration
-                    }))
+                let prev = entries[i - 1].timestamp;
+                entry.timestamp > prev
+                    && (entry.timestamp >= *start_time + session_duration + grace
+                        || entry.timestamp - prev >= session_duration)
             }
         };
 
         if need_new_block {
-            let start_time = round_to_hour(entry.timestamp);
+            let start = if settings.exact_start { entry.timestamp } else { round_to_hour(entry.timestamp) };
+            bounds.push((start, i));
+        }
+    }
+
+    let skip = bounds.len().saturating_sub(max_blocks);
+    let now = Utc::now();
+
+    let mut blocks: Vec<SessionBlock> = bounds
+        .iter()
+        .enumerate()
+        .skip(skip)
+        .map(|(b, &(start_time, first))| {
+            let last = bounds.get(b + 1).map_or(entries.len(), |&(_, next)| next);
             let end_time = start_time + session_duration;
+            let block_entries = entries[first..last].to_vec();
+            let stats = aggregate(&block_entries, "Block", policy);
+
+            // Mark active blocks; a block busy up to its end stays current during the grace period
+            let busy_at_end = block_entries.last().is_some_and(|e| e.timestamp >= end_time - grace);
+            let active_until = if busy_at_end { end_time + grace } else { end_time };
 
-            blocks.push(SessionBlock {
+            SessionBlock {
                 start_time,
                 end_time,
-                is_active: false,
-                entries: Vec::new(),
-                stats: PeriodStats::default(),
-            });
-        }
-
-        // Add entry to current block
-        if let Some(block) = blocks.last_mut() {
-            block.entries.push(entry.clone());
-        }
-    }
+                is_active: active_until > now && start_time <= now,
+                entries: block_entries,
+                stats,
+            }
+        })
+        .collect();
 
-    // Mark active blocks and calculate stats
-    let now = Utc::now();
-    for block in &mut blocks {
-        block.is_active = block.end_time > now && block.start_time <= now;
-        block.stats = aggregate(&block.entries, "Block");
+    // At most one block is active: if a grace period ever overlaps the next block,
+    // the newest one (the one containing now) wins
+    let mut seen_active = false;
+    for block in blocks.iter_mut().rev() {
+        block.is_active &= !seen_active;
+        seen_active |= block.is_active;
     }
-
     blocks
 }
 
@@ -199,18 +509,191 @@ pub fn find_current_block(blocks: &[SessionBlock]) -> Option<&SessionBlock> {
     blocks.iter().find(|b| b.is_active)
 }
 
+/// End of the most recent finished block (None without history)
+pub fn last_block_end(blocks: &[SessionBlock]) -> Option<DateTime<Utc>> {
+    blocks.iter().filter(|b| !b.is_active).map(|b| b.end_time).max()
+}
+
+/// Find the newest entry if it landed within `window_secs` of now
+pub fn find_live_entry(entries: &[Entry], now: DateTime<Utc>, window_secs: i64) -> Option<&Entry> {
+    entries.last().filter(|e| {
+        let age = (now - e.timestamp).num_seconds();
+        (0..=window_secs).contains(&age)
+    })
+}
+
+/// Newest `n` blocks (all of them when fewer)
+fn last_blocks(blocks: &[SessionBlock], n: usize) -> &[SessionBlock] {
+    &blocks[blocks.len().saturating_sub(n)..]
+}
+
+/// Summarize the most recent blocks (newest first) for the history view
+pub fn get_block_history(blocks: &[SessionBlock], max_blocks: usize) -> Vec<BlockSummary> {
+    last_blocks(blocks, max_blocks)
+        .iter()
+        .rev()
+        .map(|block| BlockSummary {
+            start_time: block.start_time,
+            end_time: block.end_time,
+            is_active: block.is_active,
+            limit_cost: block.stats.limit_cost,
+            limit_tokens: block.stats.limit_tokens,
+            messages: block.stats.total_calls,
+        })
+        .collect()
+}
+
+/// Percentage of a limit (0 when the limit is unset)
+fn percent_of(value: f64, limit: f64) -> f64 {
+    if limit > 0.0 {
+        (value / limit) * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Start times of completed blocks starting at or after `since` that reached
+/// `threshold_percent` of any plan limit
+/// Pass blocks built from all entries, so one straddling `since` keeps its earlier calls
+pub fn find_maxed_blocks(
+    blocks: &[SessionBlock],
+    plan: &PlanLimits,
+    threshold_percent: f64,
+    since: DateTime<Utc>,
+) -> Vec<DateTime<Utc>> {
+    blocks
+        .iter()
+        .filter(|block| !block.is_active && block.start_time >= since)
+        .filter(|block| {
+            let stats = &block.stats;
+            percent_of(stats.limit_cost, plan.cost_limit) >= threshold_percent
+                || percent_of(stats.limit_tokens as f64, plan.token_limit as f64) >= threshold_percent
+                || percent_of(stats.total_calls as f64, plan.message_limit as f64) >= threshold_percent
+        })
+        .map(|block| block.start_time)
+        .collect()
+}
+
+/// Limit cost (in cents) of each of the last `n` blocks, oldest first
+/// Returns fewer values when less history exists
+pub fn recent_block_costs(blocks: &[SessionBlock], n: usize) -> Vec<u64> {
+    last_blocks(blocks, n)
+        .iter()
+        .map(|block| (block.stats.limit_cost * 100.0).round() as u64)
+        .collect()
+}
+
+/// Limit cost (in cents) per local weekday (0 = Mon) and hour of day
+pub fn usage_heatmap(entries: &[Entry], zone: Zone, policy: &LimitPolicy) -> [[u64; 24]; 7] {
+    let mut grid = [[0u64; 24]; 7];
+    for entry in entries {
+        let local = zone.naive(entry.timestamp);
+        let day = local.weekday().num_days_from_monday() as usize;
+        grid[day][local.hour() as usize] += (calculate_entry_limit_cost(entry, policy) * 100.0).round() as u64;
+    }
+    grid
+}
+
+/// Limit cost per local weekday (0 = Mon), days split at `day_start_hour` like filter_this_month
+pub fn cost_by_weekday(entries: &[Entry], day_start_hour: u32, zone: Zone, policy: &LimitPolicy) -> [f64; 7] {
+    let mut costs = [0.0; 7];
+    for entry in entries {
+        let day = local_day(entry.timestamp, day_start_hour, zone).weekday().num_days_from_monday() as usize;
+        costs[day] += calculate_entry_limit_cost(entry, policy);
+    }
+    costs
+}
+
+/// Limit cost per local day for the last `days` days (oldest first, gaps = 0)
+/// Days split at `day_start_hour` like filter_today
+pub fn daily_cost_series(
+    entries: &[Entry],
+    days: usize,
+    day_start_hour: u32,
+    zone: Zone,
+    policy: &LimitPolicy,
+) -> Vec<(NaiveDate, f64)> {
+    let today = local_day(Utc::now(), day_start_hour, zone);
+    let mut series: Vec<(NaiveDate, f64)> = (0..days)
+        .rev()
+        .map(|back| (today - Duration::days(back as i64), 0.0))
+        .collect();
+
+    let Some(&(first_day, _)) = series.first() else {
+        return series;
+    };
+    for entry in entries {
+        let day = local_day(entry.timestamp, day_start_hour, zone);
+        if day < first_day || day > today {
+            continue;
+        }
+        let index = (day - first_day).num_days() as usize;
+        series[index].1 += calculate_entry_limit_cost(entry, policy);
+    }
+    series
+}
+
+/// Minutes between two timestamps that fall inside the work hours
+pub fn work_minutes_between(from: DateTime<Utc>, to: DateTime<Utc>, hours: &WorkHours, zone: Zone) -> f64 {
+    if hours.is_full_day() {
+        return (to - from).num_seconds() as f64 / 60.0;
+    }
+
+    // Walk hour by hour (local), counting only the in-hours slices
+    let mut secs = 0i64;
+    let mut t = from;
+    while t < to {
+        let local = zone.naive(t);
+        let into_hour = (local.minute() * 60 + local.second()) as i64;
+        let next = (t + Duration::seconds(3600 - into_hour)).min(to);
+        if hours.contains(t, zone) {
+            secs += (next - t).num_seconds();
+        }
+        t = next;
+    }
+    secs as f64 / 60.0
+}
+
+/// Compare the earliest predicted exhaustion against the reset time
+pub fn reset_verdict(
+    tokens_exhausted_at: Option<DateTime<Utc>>,
+    cost_exhausted_at: Option<DateTime<Utc>>,
+    reset_time: DateTime<Utc>,
+) -> ResetVerdict {
+    let earliest = match (tokens_exhausted_at, cost_exhausted_at) {
+        (Some(t), Some(c)) => t.min(c),
+        (Some(t), None) => t,
+        (None, Some(c)) => c,
+        (None, None) => return ResetVerdict::WillSurvive,
+    };
+
+    if earliest < reset_time {
+        ResetVerdict::WillExhaust {
+            before_secs: (reset_time - earliest).num_seconds(),
+        }
+    } else {
+        ResetVerdict::WillSurvive
+    }
+}
+
 /// Get current block info for display with all metrics
-pub fn get_current_block_info(entries: &[Entry], plan: &PlanLimits) -> CurrentBlockInfo {
+/// `blocks` from create_blocks with `config`'s settings; only the newest one is read
+pub fn get_current_block_info(blocks: &[SessionBlock], plan: &PlanLimits, config: &Config) -> CurrentBlockInfo {
     let now = Utc::now();
 
-    // Use the proper block creation logic that handles gaps correctly
-    let blocks = create_blocks(entries);
-    let current_block = find_current_block(&blocks);
+    // Only the latest block can be active
+    let blocks = last_blocks(blocks, 1);
+    let current_block = find_current_block(blocks);
 
     // If no active block, return empty (session has reset)
     let block = match current_block {
         Some(b) => b,
-        None => return CurrentBlockInfo::default(),
+        None => {
+            return CurrentBlockInfo {
+                last_block_end: last_block_end(blocks),
+                ..Default::default()
+            }
+        }
     };
 
     let block_start = block.start_time;
@@ -225,43 +708,32 @@ pub fn get_current_block_info(entries: &[Entry], plan: &PlanLimits) -> CurrentBl
     let mut real_tokens = 0u64;
 
     for entry in &block.entries {
-        limit_cost += calculate_entry_limit_cost(entry);
-        limit_tokens += get_limit_tokens(entry);
+        limit_cost += calculate_entry_limit_cost(entry, &config.limit_policy);
+        limit_tokens = limit_tokens.saturating_add(get_limit_tokens(entry, &config.limit_policy));
         real_cost += calculate_entry_cost(entry);
-        real_tokens += entry.usage.total();
+        real_tokens = real_tokens.saturating_add(entry.usage.total());
     }
 
     // Calculate percentages
-    let cost_percent = if plan.cost_limit > 0.0 {
-        (limit_cost / plan.cost_limit) * 100.0
-    } else {
-        0.0
-    };
-
-    let tokens_percent = if plan.token_limit > 0 {
-        (limit_tokens as f64 / plan.token_limit as f64) * 100.0
-    } else {
-        0.0
-    };
-
-    let messages_percent = if plan.message_limit > 0 {
-        (limit_messages as f64 / plan.message_limit as f64) * 100.0
-    } else {
-        0.0
-    };
-
-    // Calculate burn rate
-    let active_minutes = if block.entries.len() > 1 {
-        let first_ts = block.entries.first().unwrap().timestamp;
-        let last_ts = block.entries.last().unwrap().timestamp;
-        let duration_mins = (last_ts - first_ts).num_seconds() as f64 / 60.0;
-        duration_mins.max(1.0)
+    let cost_percent = percent_of(limit_cost, plan.cost_limit);
+    let tokens_percent = percent_of(limit_tokens as f64, plan.token_limit as f64);
+    let messages_percent = percent_of(limit_messages as f64, plan.message_limit as f64);
+
+    // Calculate burn rate (only entries and time inside the work hours)
+    let hours = &config.work_hours;
+    let work_entries: Vec<&Entry> = block.entries.iter().filter(|e| hours.contains(e.timestamp, config.timezone)).collect();
+    let active_minutes = if work_entries.len() > 1 {
+        let first_ts = work_entries.first().unwrap().timestamp;
+        let last_ts = work_entries.last().unwrap().timestamp;
+        work_minutes_between(first_ts, last_ts, hours, config.timezone).max(1.0)
     } else {
         1.0
     };
 
-    let tokens_per_min = limit_tokens as f64 / active_minutes;
-    let cost_per_min = limit_cost / active_minutes;
+    let work_tokens = work_entries.iter().map(|e| get_limit_tokens(e, &config.limit_policy)).fold(0, u64::saturating_add);
+    let work_cost: f64 = work_entries.iter().map(|e| calculate_entry_limit_cost(e, &config.limit_policy)).sum();
+    let tokens_per_min = work_tokens as f64 / active_minutes;
+    let cost_per_min = work_cost / active_minutes;
 
     // Calculate predictions
     let tokens_remaining = if limit_tokens < plan.token_limit {
@@ -293,6 +765,28 @@ pub fn get_current_block_info(entries: &[Entry], plan: &PlanLimits) -> CurrentBl
         None
     };
 
+    let tier_limits = plan
+        .tier_cost_limits
+        .iter()
+        .map(|(tier, &cost_limit)| {
+            let tier_cost: f64 = block
+                .entries
+                .iter()
+                .filter(|e| get_tier(&e.model).eq_ignore_ascii_case(tier))
+                .map(|e| calculate_entry_limit_cost(e, &config.limit_policy))
+                .sum();
+            TierLimitUsage {
+                tier: tier.clone(),
+                limit_cost: tier_cost,
+                cost_limit,
+                percent: percent_of(tier_cost, cost_limit),
+            }
+        })
+        .collect();
+
+    let verdict = reset_verdict(tokens_exhausted_at, cost_exhausted_at, block_end);
+    let live_entry = find_live_entry(&block.entries, now, config.live_window_secs);
+
     CurrentBlockInfo {
         block_start: Some(block_start),
         reset_time: Some(block_end),
@@ -305,142 +799,1600 @@ pub fn get_current_block_info(entries: &[Entry], plan: &PlanLimits) -> CurrentBl
         cost_percent,
         tokens_percent,
         messages_percent,
+        tier_limits,
         tokens_per_min,
         cost_per_min,
         active_minutes,
         tokens_exhausted_at,
         cost_exhausted_at,
+        verdict,
+        live_call: live_entry.is_some(),
+        live_call_cost: live_entry.map_or(0.0, |e| calculate_entry_limit_cost(e, &config.limit_policy)),
+        hourly_tokens: block_hourly_tokens(block, now, &config.limit_policy),
         is_active: block.is_active,
+        last_block_end: None,
     }
 }
 
-/// Get model distribution for current active block only
-pub fn get_model_distribution(entries: &[Entry]) -> Vec<ModelDistribution> {
-    // Use the proper block system (same as get_current_block_info)
-    let blocks = create_blocks(entries);
-    let current_block = find_current_block(&blocks);
+/// Limit tokens (per `policy`) per hour slot of a block, relative to its start
+/// Only slots reached by `now` are returned (at most 5)
+pub fn block_hourly_tokens(block: &SessionBlock, now: DateTime<Utc>, policy: &LimitPolicy) -> Vec<u64> {
+    let slots = (block.end_time - block.start_time).num_hours().max(0) as usize;
+    let reached = ((now - block.start_time).num_hours().max(0) as usize + 1).min(slots);
+    let mut buckets = vec![0u64; reached];
+    for entry in &block.entries {
+        let hour = (entry.timestamp - block.start_time).num_hours().max(0) as usize;
+        if let Some(bucket) = buckets.get_mut(hour) {
+            *bucket = bucket.saturating_add(get_limit_tokens(entry, policy));
+        }
+    }
+    buckets
+}
+
+/// Instant of the start of `hour` on `date` in `zone`
+fn local_at_hour(date: NaiveDate, hour: u32, zone: Zone) -> DateTime<Utc> {
+    zone.to_utc(date.and_hms_opt(hour.min(23), 0, 0).unwrap_or_default())
+}
+
+/// Most recent weekly reset at or before `now`
+pub fn last_week_reset(now: DateTime<Utc>, reset: &WeekReset, zone: Zone) -> DateTime<Utc> {
+    let today = zone.naive(now).date();
+    let days_back = (7 + today.weekday().num_days_from_monday() - reset.weekday.num_days_from_monday()) % 7;
+    let start_day = today - Duration::days(days_back as i64);
+    let start = local_at_hour(start_day, reset.hour, zone);
+
+    // Reset hour not reached yet today = still in last week's window
+    if start > now {
+        local_at_hour(start_day - Duration::days(7), reset.hour, zone)
+    } else {
+        start
+    }
+}
+
+/// Get usage since the last weekly reset, against the window's optional cost cap
+pub fn get_weekly_block_info(entries: &[Entry], week_reset: &WeekReset, zone: Zone, policy: &LimitPolicy) -> WeeklyBlockInfo {
+    let now = Utc::now();
+    let week_start = last_week_reset(now, week_reset, zone);
+    let reset_time = local_at_hour(zone.naive(week_start).date() + Duration::days(7), week_reset.hour, zone);
+
+    let mut limit_cost = 0.0;
+    let mut limit_tokens = 0u64;
+    let mut limit_messages = 0u64;
+    for entry in entries.iter().filter(|e| e.timestamp >= week_start && e.timestamp < reset_time) {
+        limit_cost += calculate_entry_limit_cost(entry, policy);
+        limit_tokens = limit_tokens.saturating_add(get_limit_tokens(entry, policy));
+        limit_messages += 1;
+    }
+
+    let cost_percent = percent_of(limit_cost, week_reset.cost_limit);
+
+    WeeklyBlockInfo {
+        week_start: Some(week_start),
+        reset_time: Some(reset_time),
+        secs_until_reset: (reset_time - now).num_seconds().max(0),
+        limit_cost,
+        limit_tokens,
+        limit_messages,
+        cost_limit: week_reset.cost_limit,
+        cost_percent,
+    }
+}
+
+/// Rolling window used for the plan's weekly caps
+const WEEKLY_LIMIT_DAYS: i64 = 7;
+
+/// Usage over the last 7 days against the plan's weekly caps
+/// Disabled (all zero) when the plan sets neither cap
+pub fn get_weekly_limit_info(entries: &[Entry], plan: &PlanLimits, policy: &LimitPolicy) -> WeeklyLimitInfo {
+    if plan.weekly_cost_limit <= 0.0 && plan.weekly_token_limit == 0 {
+        return WeeklyLimitInfo::default();
+    }
+
+    let window_start = Utc::now() - Duration::days(WEEKLY_LIMIT_DAYS);
+    let window: Vec<&Entry> = entries.iter().filter(|e| e.timestamp >= window_start).collect();
+    let limit_cost: f64 = window.iter().map(|e| calculate_entry_limit_cost(e, policy)).sum();
+    let limit_tokens = window.iter().map(|e| get_limit_tokens(e, policy)).fold(0, u64::saturating_add);
+
+    WeeklyLimitInfo {
+        enabled: true,
+        limit_cost,
+        limit_tokens,
+        cost_limit: plan.weekly_cost_limit,
+        token_limit: plan.weekly_token_limit,
+        cost_percent: percent_of(limit_cost, plan.weekly_cost_limit),
+        tokens_percent: percent_of(limit_tokens as f64, plan.weekly_token_limit as f64),
+        reset_estimate: window.iter().map(|e| e.timestamp).min().map(|t| t + Duration::days(WEEKLY_LIMIT_DAYS)),
+    }
+}
+
+/// Does the usage fit within all of a plan's limits? (0 = unlimited)
+fn fits_plan(plan: &PlanLimits, cost: f64, tokens: u64, messages: u64) -> bool {
+    (plan.cost_limit <= 0.0 || cost <= plan.cost_limit)
+        && (plan.token_limit == 0 || tokens <= plan.token_limit)
+        && (plan.message_limit == 0 || messages <= plan.message_limit)
+}
+
+/// Plan size for ordering: cost, then tokens, then messages limit (0 = unlimited = largest)
+fn plan_size(plan: &PlanLimits) -> [f64; 3] {
+    let limit = |value: f64| if value > 0.0 { value } else { f64::INFINITY };
+    [limit(plan.cost_limit), limit(plan.token_limit as f64), limit(plan.message_limit as f64)]
+}
+
+fn cmp_plan_size(a: &PlanLimits, b: &PlanLimits) -> std::cmp::Ordering {
+    plan_size(a)
+        .iter()
+        .zip(plan_size(b).iter())
+        .map(|(x, y)| x.total_cmp(y))
+        .find(|o| o.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Index of the smallest plan, by limits, that is at least `current` and fits the block usage
+/// Plans are ordered by size rather than index (the custom plan is appended last);
+/// never switches down and returns the largest plan if nothing fits
+pub fn fitting_plan_index(plans: &[PlanLimits], current: usize, block: &CurrentBlockInfo) -> usize {
+    let current = current.min(plans.len().saturating_sub(1));
+    let Some(current_plan) = plans.get(current) else {
+        return current;
+    };
+
+    let mut candidates: Vec<usize> = (0..plans.len())
+        .filter(|&i| i == current || cmp_plan_size(&plans[i], current_plan).is_gt())
+        .collect();
+    candidates.sort_by(|&a, &b| cmp_plan_size(&plans[a], &plans[b]));
+
+    candidates
+        .iter()
+        .copied()
+        .find(|&i| fits_plan(&plans[i], block.limit_cost, block.limit_tokens, block.limit_messages))
+        .or(candidates.last().copied())
+        .unwrap_or(current)
+}
+
+/// Headroom a plan needs over the peak block to be inferred (10%)
+const INFER_HEADROOM: f64 = 1.1;
+
+/// Guess the plan from the peak limit cost/tokens/messages of completed blocks
+/// Returns the smallest plan by limits (0 = unlimited) with headroom over the peak; None
+/// without history, when the peak exceeds every plan or sits too close to a plan's limit to tell
+pub fn infer_plan(plans: &[PlanLimits], blocks: &[SessionBlock]) -> Option<usize> {
+    let completed: Vec<&SessionBlock> = blocks.iter().filter(|b| !b.is_active).collect();
+    if completed.is_empty() {
+        return None;
+    }
+    let peak_cost = completed.iter().map(|b| b.stats.limit_cost).fold(0.0, f64::max);
+    let peak_tokens = completed.iter().map(|b| b.stats.limit_tokens).max().unwrap_or(0);
+    let peak_messages = completed.iter().map(|b| b.stats.total_calls).max().unwrap_or(0);
+
+    let mut by_size: Vec<usize> = (0..plans.len()).collect();
+    by_size.sort_by(|&a, &b| cmp_plan_size(&plans[a], &plans[b]));
+    let index = by_size
+        .into_iter()
+        .find(|&i| fits_plan(&plans[i], peak_cost, peak_tokens, peak_messages))?;
+    let with_headroom = |value: f64| value * INFER_HEADROOM;
+    let comfortable = fits_plan(
+        &plans[index],
+        with_headroom(peak_cost),
+        with_headroom(peak_tokens as f64) as u64,
+        with_headroom(peak_messages as f64) as u64,
+    );
+    comfortable.then_some(index)
+}
+
+/// Get model distribution for current active block only, one row per tier
+pub fn get_model_distribution(blocks: &[SessionBlock], plan: &PlanLimits, policy: &LimitPolicy) -> Vec<ModelDistribution> {
+    block_distribution(blocks, plan, policy, |model| get_tier(model).to_string())
+}
+
+/// Same as get_model_distribution, one row per model version ("sonnet-4", "3-5-sonnet")
+pub fn get_model_version_distribution(
+    blocks: &[SessionBlock],
+    plan: &PlanLimits,
+    policy: &LimitPolicy,
+) -> Vec<ModelDistribution> {
+    block_distribution(blocks, plan, policy, short_model_name)
+}
+
+/// Current block's usage grouped by `key(model)`, sorted by cost descending
+fn block_distribution(
+    blocks: &[SessionBlock],
+    plan: &PlanLimits,
+    policy: &LimitPolicy,
+    key: impl Fn(&str) -> String,
+) -> Vec<ModelDistribution> {
+    // Same block as get_current_block_info
+    let current_block = find_current_block(last_blocks(blocks, 1));
 
     let block = match current_block {
         Some(b) => b,
         None => return Vec::new(),
     };
 
-    let mut dist_map: HashMap<String, (u64, u64, f64)> = HashMap::new(); // calls, tokens, cost
+    let mut dist_map: HashMap<String, (&'static str, u64, u64, f64)> = HashMap::new(); // tier, calls, tokens, cost
     let mut total_cost = 0.0;
 
     for entry in &block.entries {
-        let tier = get_tier(&entry.model);
-        let cost = calculate_entry_limit_cost(entry);
-        let tokens = get_limit_tokens(entry);
+        let cost = calculate_entry_limit_cost(entry, policy);
+        let tokens = get_limit_tokens(entry, policy);
         total_cost += cost;
 
-        let e = dist_map.entry(tier.to_string()).or_insert((0, 0, 0.0));
-        e.0 += 1;
-        e.1 += tokens;
-        e.2 += cost;
+        let e = dist_map.entry(key(&entry.model)).or_insert((get_tier(&entry.model), 0, 0, 0.0));
+        e.1 += 1;
+        e.2 = e.2.saturating_add(tokens);
+        e.3 += cost;
     }
 
     let mut result: Vec<ModelDistribution> = dist_map
         .into_iter()
-        .map(|(tier, (calls, tokens, cost))| {
+        .map(|(model, (tier, calls, tokens, cost))| {
             let percent = if total_cost > 0.0 {
                 (cost / total_cost) * 100.0
             } else {
                 0.0
             };
             ModelDistribution {
-                model: tier.clone(),
-                tier,
+                model,
+                tier: tier.to_string(),
                 calls,
                 tokens,
                 cost,
                 percent,
+                limit_percent: percent_of(cost, plan.cost_limit),
             }
         })
         .collect();
 
-    // Sort by cost descending
-    result.sort_by(|a, b| b.cost.partial_cmp(&a.cost).unwrap_or(std::cmp::Ordering::Equal));
+    // Sort by cost descending, then name (HashMap order must not leak into the UI)
+    result.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| a.model.cmp(&b.model)));
     result
 }
 
+/// Local "day" a timestamp belongs to, when days start at `day_start_hour`
+/// (e.g. with 4, 02:00 still counts as the previous day)
+pub fn local_day(ts: DateTime<Utc>, day_start_hour: u32, zone: Zone) -> NaiveDate {
+    (zone.naive(ts) - Duration::hours(day_start_hour.min(23) as i64)).date()
+}
+
 /// Filter entries for today only
-pub fn filter_today(entries: &[Entry]) -> Vec<Entry> {
-    let today = Local::now().date_naive();
+pub fn filter_today(entries: &[Entry], day_start_hour: u32, zone: Zone) -> Vec<Entry> {
+    let today = local_day(Utc::now(), day_start_hour, zone);
     entries
         .iter()
-        .filter(|e| e.timestamp.with_timezone(&Local).date_naive() == today)
+        .filter(|e| local_day(e.timestamp, day_start_hour, zone) == today)
+        .cloned()
+        .collect()
+}
+
+/// Filter entries for the previous local day
+pub fn filter_yesterday(entries: &[Entry], day_start_hour: u32, zone: Zone) -> Vec<Entry> {
+    let yesterday = local_day(Utc::now(), day_start_hour, zone) - Duration::days(1);
+    entries
+        .iter()
+        .filter(|e| local_day(e.timestamp, day_start_hour, zone) == yesterday)
+        .cloned()
+        .collect()
+}
+
+/// Filter entries for the previous calendar week (Mon-Sun)
+pub fn filter_last_week(entries: &[Entry], day_start_hour: u32, zone: Zone) -> Vec<Entry> {
+    let today = local_day(Utc::now(), day_start_hour, zone);
+    let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let last_monday = this_monday - Duration::days(7);
+
+    entries
+        .iter()
+        .filter(|e| {
+            let entry_date = local_day(e.timestamp, day_start_hour, zone);
+            entry_date >= last_monday && entry_date < this_monday
+        })
         .cloned()
         .collect()
 }
 
 /// Filter entries for this week (Mon-Sun)
-pub fn filter_this_week(entries: &[Entry]) -> Vec<Entry> {
-    use chrono::Datelike;
-    let now = Local::now();
-    let today = now.date_naive();
+pub fn filter_this_week(entries: &[Entry], day_start_hour: u32, zone: Zone) -> Vec<Entry> {
+    let today = local_day(Utc::now(), day_start_hour, zone);
     let days_since_monday = today.weekday().num_days_from_monday();
     let monday = today - Duration::days(days_since_monday as i64);
 
     entries
         .iter()
         .filter(|e| {
-            let entry_date = e.timestamp.with_timezone(&Local).date_naive();
+            let entry_date = local_day(e.timestamp, day_start_hour, zone);
             entry_date >= monday && entry_date <= today
         })
         .cloned()
         .collect()
 }
 
+/// Filter entries since the last weekly reset (rolling 7 days from the anchor)
+pub fn filter_plan_week(entries: &[Entry], reset: &WeekReset, zone: Zone) -> Vec<Entry> {
+    let start = last_week_reset(Utc::now(), reset, zone);
+    entries.iter().filter(|e| e.timestamp >= start).cloned().collect()
+}
+
+/// Filter entries for the weekly window before the current one
+pub fn filter_previous_plan_week(entries: &[Entry], reset: &WeekReset, zone: Zone) -> Vec<Entry> {
+    let end = last_week_reset(Utc::now(), reset, zone);
+    let start = last_week_reset(end - Duration::seconds(1), reset, zone);
+    entries
+        .iter()
+        .filter(|e| e.timestamp >= start && e.timestamp < end)
+        .cloned()
+        .collect()
+}
+
+/// Start of the current local month, when days start at `day_start_hour`
+pub fn this_month_start(now: DateTime<Utc>, day_start_hour: u32, zone: Zone) -> DateTime<Utc> {
+    let today = local_day(now, day_start_hour, zone);
+    local_at_hour(today.with_day(1).unwrap_or(today), day_start_hour, zone)
+}
+
 /// Filter entries for this month
-pub fn filter_this_month(entries: &[Entry]) -> Vec<Entry> {
-    use chrono::Datelike;
-    let now = Local::now();
-    let this_month = now.month();
-    let this_year = now.year();
+pub fn filter_this_month(entries: &[Entry], day_start_hour: u32, zone: Zone) -> Vec<Entry> {
+    let today = local_day(Utc::now(), day_start_hour, zone);
+    let this_month = today.month();
+    let this_year = today.year();
 
     entries
         .iter()
         .filter(|e| {
-            let ts = e.timestamp.with_timezone(&Local);
-            ts.month() == this_month && ts.year() == this_year
+            let day = local_day(e.timestamp, day_start_hour, zone);
+            day.month() == this_month && day.year() == this_year
         })
         .cloned()
         .collect()
 }
 
+/// Smallest elapsed fraction used for projections (avoids 50x at 00:30)
+const MIN_ELAPSED_FRACTION: f64 = 0.1;
+
+/// Extrapolate a period's cost (limit or billed) from the fraction of the period elapsed
+pub fn project_period_cost(cost: f64, elapsed_fraction: f64) -> f64 {
+    cost / elapsed_fraction.clamp(MIN_ELAPSED_FRACTION, 1.0)
+}
+
+/// Fractions of the current day and month elapsed at `now`
+pub fn elapsed_fractions(now: DateTime<Utc>, day_start_hour: u32, zone: Zone) -> (f64, f64) {
+    let today = local_day(now, day_start_hour, zone);
+    let day_start = today.and_hms_opt(day_start_hour.min(23), 0, 0).unwrap_or_default();
+    let day_fraction = ((zone.naive(now) - day_start).num_seconds() as f64 / 86_400.0).clamp(0.0, 1.0);
+
+    let month_start = today.with_day(1).unwrap_or(today);
+    let next_month = (month_start + Duration::days(32)).with_day(1).unwrap_or(month_start);
+    let days_in_month = (next_month - month_start).num_days().max(1) as f64;
+    let month_fraction = (today.day0() as f64 + day_fraction) / days_in_month;
+
+    (day_fraction, month_fraction)
+}
+
+/// Days elapsed (fractional, current day included) in this week and this month
+/// The week starts Monday, or at the weekly reset when `week_reset` is given
+pub fn elapsed_days(now: DateTime<Utc>, day_start_hour: u32, zone: Zone, week_reset: Option<&WeekReset>) -> (f64, f64) {
+    let today = local_day(now, day_start_hour, zone);
+    let day_fraction = elapsed_fractions(now, day_start_hour, zone).0;
+    let week_days = match week_reset {
+        Some(reset) => (now - last_week_reset(now, reset, zone)).num_seconds() as f64 / 86_400.0,
+        None => today.weekday().num_days_from_monday() as f64 + day_fraction,
+    };
+    (week_days, today.day0() as f64 + day_fraction)
+}
+
+/// Divisor for a per-day average over `elapsed_days`
+/// The current partial day counts as a full day, or only for its elapsed part when `prorate`
+pub fn average_days(elapsed_days: f64, prorate: bool) -> f64 {
+    let days = if prorate { elapsed_days } else { elapsed_days.floor() + 1.0 };
+    days.max(MIN_ELAPSED_FRACTION)
+}
+
+/// Compare today, this week and this month (limit cost)
+/// `days` = days elapsed in the week and month (see elapsed_days)
+/// `elapsed` = fractions of the day and month elapsed (see elapsed_fractions)
+pub fn compute_trends(
+    today: &PeriodStats,
+    week: &PeriodStats,
+    month: &PeriodStats,
+    days: (f64, f64),
+    elapsed: (f64, f64),
+    prorate_today: bool,
+) -> Trends {
+    let month_daily_avg = month.limit_cost / average_days(days.1, prorate_today);
+
+    Trends {
+        today_of_week_percent: percent_of(today.limit_cost, week.limit_cost),
+        week_of_month_percent: percent_of(week.limit_cost, month.limit_cost),
+        week_daily_avg: week.limit_cost / average_days(days.0, prorate_today),
+        month_daily_avg,
+        accelerating: today.limit_cost > month_daily_avg,
+        projected_end_of_day: project_period_cost(today.limit_cost, elapsed.0),
+        projected_end_of_month: project_period_cost(month.limit_cost, elapsed.1),
+        projected_month_bill: project_period_cost(month.total_cost, elapsed.1),
+    }
+}
+
 /// Aggregate entries into stats
-pub fn aggregate(entries: &[Entry], label: &str) -> PeriodStats {
-    let mut models_map: HashMap<String, ModelStats> = HashMap::new();
-    let mut sessions: HashSet<String> = HashSet::new();
+pub fn aggregate(entries: &[Entry], label: &str, policy: &LimitPolicy) -> PeriodStats {
+    // Keyed by borrowed names: no per-entry String clones on large histories
+    let mut models_map: HashMap<&str, ModelStats> = HashMap::new();
+    let mut sessions: HashSet<&str> = HashSet::new();
+    let mut limit_cost = 0.0;
+    let mut limit_tokens = 0u64;
 
     for entry in entries {
-        sessions.insert(entry.session_id.clone());
+        sessions.insert(&entry.session_id);
+        limit_cost += calculate_entry_limit_cost(entry, policy);
+        limit_tokens = limit_tokens.saturating_add(get_limit_tokens(entry, policy));
 
         let stats = models_map
-            .entry(entry.model.clone())
+            .entry(&entry.model)
             .or_insert_with(|| ModelStats::new(entry.model.clone()));
-        stats.add(&entry.usage);
+        stats.add(&entry.usage, calculate_entry_cost(entry));
     }
 
     let mut models: Vec<ModelStats> = models_map.into_values().collect();
-    // Sort by cost descending
-    models.sort_by(|a, b| {
-        calculate_cost(b)
-            .partial_cmp(&calculate_cost(a))
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-
-    let total_tokens: u64 = models.iter().map(|m| m.total_tokens()).sum();
+    for m in &mut models {
+        m.cache_hit_percent = m.cache_hit_ratio() * 100.0;
+        m.cache_savings = m.estimated_cache_savings();
+        m.cost_per_output_mtok = m.cost_per_output_mtok();
+    }
+    // Sort by cost descending, then name (HashMap order must not leak into the UI)
+    models.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| a.model.cmp(&b.model)));
+
+    let total_tokens = models.iter().map(|m| m.total_tokens()).fold(0, u64::saturating_add);
     let total_calls: u64 = models.iter().map(|m| m.call_count).sum();
-    let total_cost: f64 = models.iter().map(|m| calculate_cost(m)).sum();
+    let total_cost: f64 = models.iter().map(|m| m.cost).sum();
+    let total_output = models.iter().map(|m| m.output_tokens).fold(0, u64::saturating_add);
 
-    PeriodStats {
+    let mut stats = PeriodStats {
         models,
         total_tokens,
         total_cost,
         total_calls,
+        limit_cost,
+        limit_tokens,
         session_count: sessions.len(),
         period_label: label.to_string(),
+        first_timestamp: entries.iter().map(|e| e.timestamp).min(),
+        last_timestamp: entries.iter().map(|e| e.timestamp).max(),
+        cost_per_output_mtok: cost_per_output_mtok(total_cost, total_output),
+        ..Default::default()
+    };
+    stats.avg_cost_per_session = stats.avg_cost_per_session();
+    stats.avg_tokens_per_session = stats.avg_tokens_per_session();
+    stats
+}
+
+/// Aggregate entries per project, sorted by cost descending
+pub fn aggregate_by_project(entries: &[Entry], policy: &LimitPolicy) -> Vec<(String, PeriodStats)> {
+    let mut by_project: HashMap<String, Vec<Entry>> = HashMap::new();
+    for entry in entries {
+        by_project.entry(entry.project.clone()).or_default().push(entry.clone());
+    }
+
+    let mut result: Vec<(String, PeriodStats)> = by_project
+        .into_iter()
+        .map(|(project, entries)| {
+            let stats = aggregate(&entries, &project, policy);
+            (project, stats)
+        })
+        .collect();
+
+    result.sort_by(|a, b| b.1.total_cost.total_cmp(&a.1.total_cost).then_with(|| a.0.cmp(&b.0)));
+    result
+}
+
+/// Cost per tier from a period's models, sorted per `order`
+/// Sorted after summing, so the order never depends on which model came first
+pub fn tier_breakdown(stats: &PeriodStats, order: TierOrder) -> Vec<TierCost> {
+    let mut tiers: Vec<TierCost> = Vec::new();
+    for model in &stats.models {
+        match tiers.iter_mut().find(|t| t.tier == model.tier) {
+            Some(t) => {
+                t.cost += model.cost;
+                t.calls += model.call_count;
+            }
+            None => tiers.push(TierCost {
+                tier: model.tier.clone(),
+                cost: model.cost,
+                calls: model.call_count,
+            }),
+        }
+    }
+
+    let by_family = |a: &TierCost, b: &TierCost| tier_rank(&a.tier).cmp(&tier_rank(&b.tier)).then(a.tier.cmp(&b.tier));
+    match order {
+        TierOrder::Cost => tiers.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| by_family(a, b))),
+        TierOrder::Family => tiers.sort_by(by_family),
+    }
+    tiers
+}
+
+/// Calls closer than this to the previous one are parallel requests, not a fallback
+const PARALLEL_CALL_SECS: i64 = 1;
+
+/// Count downgrades (Opus -> Sonnet, Sonnet -> Haiku, ...) between consecutive calls of each session
+/// A lower-tier call within PARALLEL_CALL_SECS of the previous one is a parallel request, not a fallback
+pub fn tier_fallbacks(entries: &[Entry]) -> TierFallbacks {
+    let mut by_session: HashMap<&str, Vec<&Entry>> = HashMap::new();
+    for entry in entries.iter().filter(|e| matches!(get_tier(&e.model), "Opus" | "Sonnet" | "Haiku")) {
+        by_session.entry(&entry.session_id).or_default().push(entry);
+    }
+
+    let mut timestamps = Vec::new();
+    for session in by_session.values_mut() {
+        session.sort_by_key(|e| e.timestamp);
+        for pair in session.windows(2) {
+            let (prev, entry) = (pair[0], pair[1]);
+            let downgrade = tier_rank(get_tier(&entry.model)) > tier_rank(get_tier(&prev.model));
+            if downgrade && entry.timestamp - prev.timestamp >= Duration::seconds(PARALLEL_CALL_SECS) {
+                timestamps.push(entry.timestamp);
+            }
+        }
+    }
+    timestamps.sort();
+
+    TierFallbacks {
+        fallback_count: timestamps.len(),
+        timestamps,
+    }
+}
+
+/// Most expensive single entry: (timestamp, model, real cost), None without entries
+pub fn max_entry_cost(entries: &[Entry]) -> Option<(DateTime<Utc>, String, f64)> {
+    entries
+        .iter()
+        .map(|e| (e, calculate_entry_cost(e)))
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(e, cost)| (e.timestamp, e.model.clone(), cost))
+}
+
+/// Aggregate entries per session id, sorted by cost descending
+pub fn aggregate_by_session(entries: &[Entry], policy: &LimitPolicy) -> Vec<(String, PeriodStats)> {
+    let mut by_session: HashMap<String, Vec<Entry>> = HashMap::new();
+    for entry in entries {
+        by_session.entry(entry.session_id.clone()).or_default().push(entry.clone());
+    }
+
+    let mut result: Vec<(String, PeriodStats)> = by_session
+        .into_iter()
+        .map(|(session, entries)| {
+            let stats = aggregate(&entries, &session, policy);
+            (session, stats)
+        })
+        .collect();
+
+    result.sort_by(|a, b| b.1.total_cost.total_cmp(&a.1.total_cost).then_with(|| a.0.cmp(&b.0)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PLANS;
+    use crate::calculator::SYSTEM_MODEL;
+
+    const UTC: Zone = Zone::Named(chrono_tz::UTC);
+
+    fn ts(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339).unwrap().with_timezone(&Utc)
+    }
+
+    fn minutes_ago(minutes: i64) -> DateTime<Utc> {
+        Utc::now() - Duration::minutes(minutes)
+    }
+
+    fn jsonl_line(timestamp: DateTime<Utc>, model: &str, session: &str, output: u64) -> String {
+        format!(
+            r#"{{"timestamp":"{}","sessionId":"{session}","message":{{"model":"{model}","usage":{{"input_tokens":100,"output_tokens":{output}}}}}}}"#,
+            timestamp.to_rfc3339()
+        )
+    }
+
+    fn entry(timestamp: DateTime<Utc>, model: &str, session: &str, output: u64) -> Entry {
+        map_str(&jsonl_line(timestamp, model, session, output)).unwrap()
+    }
+
+    /// Held by tests that point DATA_DIR_ENV at a fixture (tests run in parallel)
+    static DATA_DIR_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// Fresh scratch directory for file fixtures
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("claude-dashboard-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn sonnet(timestamp: DateTime<Utc>) -> Entry {
+        entry(timestamp, "claude-sonnet-4-20250514", "s1", 1_000)
+    }
+
+    fn no_grace() -> BlockSettings {
+        BlockSettings { grace_secs: 0, exact_start: false }
+    }
+
+    fn completed_block(limit_cost: f64, limit_tokens: u64, total_calls: u64) -> SessionBlock {
+        let start_time = Utc::now() - Duration::days(1);
+        SessionBlock {
+            start_time,
+            end_time: start_time + Duration::hours(5),
+            is_active: false,
+            entries: Vec::new(),
+            stats: PeriodStats { limit_cost, limit_tokens, total_calls, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn infer_plan_picks_the_smallest_plan_by_size_not_position() {
+        let mut plans = PLANS.clone();
+        // Custom plan between Pro and Max5, appended last
+        plans.push(PlanLimits { name: "Team".into(), token_limit: 50_000, cost_limit: 25.0, message_limit: 500, ..Default::default() });
+
+        assert_eq!(infer_plan(&plans, &[completed_block(10.0, 10_000, 10)]), Some(0));
+        assert_eq!(infer_plan(&plans, &[completed_block(20.0, 10_000, 10)]), Some(3));
+        assert_eq!(infer_plan(&plans, &[completed_block(30.0, 10_000, 10)]), Some(1));
+        // Too close to Max5's cost limit to tell
+        assert_eq!(infer_plan(&plans, &[completed_block(34.0, 10_000, 10)]), None);
+    }
+
+    #[test]
+    fn infer_plan_treats_zero_limits_as_unlimited() {
+        let mut plans = PLANS.clone();
+        plans.push(PlanLimits { name: "Unlimited".into(), ..Default::default() });
+        assert_eq!(infer_plan(&plans, &[completed_block(1_000.0, 10_000_000, 10_000)]), Some(3));
+        assert_eq!(infer_plan(&plans, &[completed_block(1.0, 1_000, 10)]), Some(0));
+        assert_eq!(infer_plan(&plans, &[]), None);
+    }
+
+    #[test]
+    fn project_key_follows_depth_or_leaf() {
+        let base = Path::new("/data/projects");
+        let file = Path::new("/data/projects/work/client/repo/session.jsonl");
+        let key = |project_depth, project_use_leaf| {
+            project_key(base, file, &Config { project_depth, project_use_leaf, ..Default::default() })
+        };
+
+        assert_eq!(key(1, false), "work");
+        assert_eq!(key(2, false), "work/client");
+        assert_eq!(key(9, false), "work/client/repo");
+        assert_eq!(key(0, false), "work");
+        assert_eq!(key(1, true), "repo");
+        // Shallower paths keep what they have
+        let shallow = Path::new("/data/projects/personal/session.jsonl");
+        assert_eq!(project_key(base, shallow, &Config { project_depth: 3, ..Default::default() }), "personal");
+        assert_eq!(project_key(base, Path::new("/data/projects/top.jsonl"), &Config::default()), "unknown");
+    }
+
+    #[test]
+    fn burn_rate_spans_the_block_and_survives_a_single_entry() {
+        let config = Config::default();
+        let plan = &PLANS[0];
+
+        let single = create_blocks(&[sonnet(minutes_ago(10))], &config.blocks, &config.limit_policy);
+        let info = get_current_block_info(&single, plan, &config);
+        assert!(info.is_active);
+        assert_eq!(info.active_minutes, 1.0);
+        assert_eq!(info.tokens_per_min, 1_000.0);
+        assert!(info.tokens_exhausted_at.is_some_and(|t| t > Utc::now()));
+
+        let pair = create_blocks(&[sonnet(minutes_ago(40)), sonnet(minutes_ago(10))], &config.blocks, &config.limit_policy);
+        let info = get_current_block_info(&pair, plan, &config);
+        assert!((info.active_minutes - 30.0).abs() < 0.1);
+        assert!((info.tokens_per_min - 2_000.0 / 30.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn live_entry_is_the_newest_one_within_the_window() {
+        let now = Utc::now();
+        let entries = vec![sonnet(now - Duration::seconds(60)), sonnet(now - Duration::seconds(5))];
+        assert_eq!(find_live_entry(&entries, now, 10).map(|e| e.timestamp), Some(entries[1].timestamp));
+        assert!(find_live_entry(&entries, now, 3).is_none());
+        assert!(find_live_entry(&entries[..1], now, 10).is_none());
+        // Clock skew: an entry from the future is not live
+        assert!(find_live_entry(&[sonnet(now + Duration::seconds(5))], now, 10).is_none());
+        assert!(find_live_entry(&[], now, 10).is_none());
+    }
+
+    #[test]
+    fn week_reset_anchors_on_the_last_reset_day_and_hour() {
+        let reset = WeekReset { weekday: chrono::Weekday::Mon, hour: 9, ..Default::default() };
+        assert_eq!(last_week_reset(ts("2025-03-05T12:00:00Z"), &reset, UTC), ts("2025-03-03T09:00:00Z"));
+        // Reset day, before and at the reset hour
+        assert_eq!(last_week_reset(ts("2025-03-10T08:59:00Z"), &reset, UTC), ts("2025-03-03T09:00:00Z"));
+        assert_eq!(last_week_reset(ts("2025-03-10T09:00:00Z"), &reset, UTC), ts("2025-03-10T09:00:00Z"));
+        let paris = Zone::Named(chrono_tz::Europe::Paris);
+        assert_eq!(last_week_reset(ts("2025-03-10T08:30:00Z"), &reset, paris), ts("2025-03-10T08:00:00Z"));
+    }
+
+    #[test]
+    fn weekly_block_counts_down_and_sums_this_week_only() {
+        let now = Utc::now();
+        let yesterday = now.date_naive() - Duration::days(1);
+        let reset = WeekReset { weekday: yesterday.weekday(), hour: 0, cost_limit: 0.03 };
+        let entries = vec![sonnet(now - Duration::days(3)), sonnet(minutes_ago(1))];
+
+        let info = get_weekly_block_info(&entries, &reset, UTC, &LimitPolicy::default());
+        let week_start = yesterday.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        assert_eq!(info.week_start, Some(week_start));
+        assert_eq!(info.reset_time, Some(week_start + Duration::days(7)));
+        assert!(info.secs_until_reset > 5 * 86_400 && info.secs_until_reset <= 6 * 86_400);
+        assert_eq!(info.limit_messages, 1);
+        assert_eq!(info.limit_tokens, 1_000);
+        assert!((info.cost_percent - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recent_blocks_keep_only_the_newest_ones() {
+        let start = ts("2025-03-01T00:00:00Z");
+        let entries: Vec<Entry> = (0..10).map(|i| sonnet(start + Duration::hours(6 * i))).collect();
+        let settings = BlockSettings::default();
+        let policy = LimitPolicy::default();
+
+        let all = create_blocks(&entries, &settings, &policy);
+        assert_eq!(all.len(), 10);
+        let recent = create_recent_blocks(&entries, 3, &settings, &policy);
+        let starts = |blocks: &[SessionBlock]| blocks.iter().map(|b| b.start_time).collect::<Vec<_>>();
+        assert_eq!(starts(&recent), starts(&all[7..]));
+        assert_eq!(create_recent_blocks(&entries, 50, &settings, &policy).len(), 10);
+        assert!(create_recent_blocks(&entries, 0, &settings, &policy).is_empty());
+    }
+
+    #[test]
+    fn recent_block_costs_are_cents_oldest_first() {
+        let blocks: Vec<SessionBlock> = [1.0, 2.5, 0.004, 3.333].iter().map(|&c| completed_block(c, 0, 1)).collect();
+        assert_eq!(recent_block_costs(&blocks, 3), vec![250, 0, 333]);
+        assert_eq!(recent_block_costs(&blocks, 12), vec![100, 250, 0, 333]);
+        assert!(recent_block_costs(&[], 12).is_empty());
+    }
+
+    #[test]
+    fn maxed_blocks_hit_any_limit_since_the_cutoff() {
+        let plan = &PLANS[0];
+        let mut old = completed_block(18.0, 0, 1);
+        old.start_time -= Duration::days(40);
+        let mut active = completed_block(18.0, 0, 1);
+        active.is_active = true;
+        let blocks = vec![
+            old,
+            completed_block(17.5, 0, 1),
+            completed_block(1.0, 19_000, 1),
+            completed_block(1.0, 0, 240),
+            completed_block(1.0, 1_000, 10),
+            active,
+        ];
+
+        let since = Utc::now() - Duration::days(30);
+        let maxed = find_maxed_blocks(&blocks, plan, 95.0, since);
+        assert_eq!(maxed.len(), 3);
+        assert!(maxed.iter().all(|&start| start >= since));
+        assert_eq!(find_maxed_blocks(&blocks, plan, 100.0, since).len(), 1);
+    }
+
+    #[test]
+    fn data_dir_env_var_points_parse_all_at_another_tree() {
+        let dir = fixture_dir("data-dir");
+        std::fs::create_dir_all(dir.join("archive")).unwrap();
+        let line = jsonl_line(minutes_ago(30), "claude-sonnet-4-20250514", "s1", 1_000);
+        std::fs::write(dir.join("archive").join("session.jsonl"), line + "\n").unwrap();
+
+        let _lock = DATA_DIR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var(DATA_DIR_ENV, &dir);
+        assert_eq!(get_data_dir(), Some(dir.clone()));
+        let entries = parse_all(&Config::default()).unwrap();
+        std::env::remove_var(DATA_DIR_ENV);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].project, "archive");
+    }
+
+    #[test]
+    fn single_file_mode_parses_just_that_file() {
+        let dir = fixture_dir("single-file");
+        let path = dir.join("usage.jsonl");
+        let lines = [
+            jsonl_line(minutes_ago(10), "claude-sonnet-4-20250514", "s1", 2_000),
+            jsonl_line(minutes_ago(60), "claude-sonnet-4-20250514", "s1", 1_000),
+            "not json".to_string(),
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        // A sibling log must not be picked up
+        std::fs::write(dir.join("other.jsonl"), jsonl_line(minutes_ago(5), "claude-sonnet-4-20250514", "s2", 1)).unwrap();
+
+        let entries = parse_single_file(&path).unwrap();
+        let missing = parse_single_file(&dir.join("missing.jsonl"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.project == "usage"));
+        assert!(entries[0].timestamp < entries[1].timestamp);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn grace_keeps_late_entries_and_a_busy_block_current() {
+        let policy = LimitPolicy::default();
+        let settings = |grace_secs| BlockSettings { grace_secs, exact_start: true };
+        let start = ts("2025-03-01T10:00:00Z");
+        let late = |secs| vec![sonnet(start), sonnet(start + Duration::hours(4)), sonnet(start + Duration::hours(5) + Duration::seconds(secs))];
+
+        assert_eq!(create_blocks(&late(9), &settings(10), &policy).len(), 1);
+        assert_eq!(create_blocks(&late(10), &settings(10), &policy).len(), 2);
+        assert_eq!(create_blocks(&late(0), &settings(0), &policy).len(), 2);
+
+        // Block ended 5 s ago, last entry 3 s before its end
+        let start = Utc::now() - Duration::hours(5) - Duration::seconds(5);
+        let entries = vec![sonnet(start), sonnet(start + Duration::hours(5) - Duration::seconds(3))];
+        let current = |grace_secs| find_current_block(&create_blocks(&entries, &settings(grace_secs), &policy)).is_some();
+        assert!(current(30));
+        assert!(!current(2));
+        assert!(!current(0));
+    }
+
+    #[test]
+    fn day_start_hour_moves_early_hours_to_the_previous_day() {
+        let night = ts("2025-03-02T02:00:00Z");
+        assert_eq!(local_day(night, 0, UTC), NaiveDate::from_ymd_opt(2025, 3, 2).unwrap());
+        assert_eq!(local_day(night, 4, UTC), NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(local_day(ts("2025-03-02T04:00:00Z"), 4, UTC), NaiveDate::from_ymd_opt(2025, 3, 2).unwrap());
+
+        // With a 04:00 day start, 02:00 belongs to the day before
+        let today = local_day(Utc::now(), 4, UTC);
+        let at_2am = |day: NaiveDate| sonnet(day.and_hms_opt(2, 0, 0).unwrap().and_utc());
+        let entries = [at_2am(today), at_2am(today + Duration::days(1))];
+        let kept = filter_today(&entries, 4, UTC);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].timestamp, entries[1].timestamp);
+        assert_eq!(filter_yesterday(&entries, 4, UTC)[0].timestamp, entries[0].timestamp);
+    }
+
+    fn usage(limit_cost: f64, limit_tokens: u64, limit_messages: u64) -> CurrentBlockInfo {
+        CurrentBlockInfo { limit_cost, limit_tokens, limit_messages, ..Default::default() }
+    }
+
+    #[test]
+    fn over_limit_usage_switches_to_the_smallest_larger_plan() {
+        let mut plans = PLANS.clone();
+        // Custom plan between Pro and Max5, appended last
+        plans.push(PlanLimits { name: "Team".into(), token_limit: 50_000, cost_limit: 25.0, message_limit: 500, ..Default::default() });
+
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(10.0, 10_000, 10)), 0);
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(20.0, 10_000, 10)), 3);
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(30.0, 10_000, 10)), 1);
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(10.0, 10_000, 600)), 1);
+        // Never switches down, and falls back to the largest plan
+        assert_eq!(fitting_plan_index(&plans, 2, &usage(1.0, 1, 1)), 2);
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(1_000.0, 1, 1)), 2);
+
+        plans.push(PlanLimits { name: "Unlimited".into(), ..Default::default() });
+        assert_eq!(fitting_plan_index(&plans, 0, &usage(1_000.0, 10_000_000, 10_000)), 4);
+    }
+
+    #[test]
+    fn aggregate_spans_the_first_and_last_entries() {
+        let entries = [sonnet(ts("2025-03-02T08:00:00Z")), sonnet(ts("2025-02-01T09:30:00Z")), sonnet(ts("2025-03-01T12:00:00Z"))];
+        let stats = aggregate(&entries, "All Time", &LimitPolicy::default());
+        assert_eq!(stats.first_timestamp, Some(ts("2025-02-01T09:30:00Z")));
+        assert_eq!(stats.last_timestamp, Some(ts("2025-03-02T08:00:00Z")));
+
+        let empty = aggregate(&[], "All Time", &LimitPolicy::default());
+        assert_eq!((empty.first_timestamp, empty.last_timestamp), (None, None));
+    }
+
+    #[test]
+    fn messages_percent_counts_block_calls_against_the_message_limit() {
+        let config = Config::default();
+        let entries: Vec<Entry> = (1..=3).map(|m| sonnet(minutes_ago(m))).collect();
+        let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+
+        let plan = PlanLimits { message_limit: 4, ..Default::default() };
+        let info = get_current_block_info(&blocks, &plan, &config);
+        assert_eq!(info.limit_messages, 3);
+        assert!((info.messages_percent - 75.0).abs() < 1e-9);
+
+        // No message cap: count shown without a percentage
+        let info = get_current_block_info(&blocks, &PlanLimits::default(), &config);
+        assert_eq!(info.limit_messages, 3);
+        assert_eq!(info.messages_percent, 0.0);
+    }
+
+    #[test]
+    fn heatmap_buckets_by_local_weekday_and_hour() {
+        let paris = Zone::Named(chrono_tz::Europe::Paris);
+        let entries = [
+            // Monday 23:30 UTC = Tuesday 00:30 in Paris
+            sonnet(ts("2025-03-03T23:30:00Z")),
+            sonnet(ts("2025-03-09T10:00:00Z")),
+            sonnet(ts("2025-03-09T10:59:00Z")),
+        ];
+        let grid = usage_heatmap(&entries, paris, &LimitPolicy::default());
+        assert_eq!(grid[1][0], 2);
+        assert_eq!(grid[6][11], 4);
+        assert_eq!(grid.iter().flatten().sum::<u64>(), 6);
+
+        assert_eq!(usage_heatmap(&entries, UTC, &LimitPolicy::default())[0][23], 2);
+    }
+
+    #[test]
+    fn work_minutes_only_count_time_inside_work_hours() {
+        let hours = WorkHours { start: 9, end: 18 };
+        // 07:30 to 10:15: only 09:00-10:15 counts
+        assert_eq!(work_minutes_between(ts("2025-03-03T07:30:00Z"), ts("2025-03-03T10:15:00Z"), &hours, UTC), 75.0);
+        // Overnight idle time is skipped entirely
+        assert_eq!(work_minutes_between(ts("2025-03-03T17:30:00Z"), ts("2025-03-04T09:30:00Z"), &hours, UTC), 60.0);
+        // The default full day counts wall-clock time
+        assert_eq!(work_minutes_between(ts("2025-03-03T17:30:00Z"), ts("2025-03-04T09:30:00Z"), &WorkHours::default(), UTC), 960.0);
+
+        let night_shift = WorkHours { start: 22, end: 6 };
+        assert!(night_shift.contains(ts("2025-03-03T23:00:00Z"), UTC));
+        assert!(night_shift.contains(ts("2025-03-03T05:59:00Z"), UTC));
+        assert!(!night_shift.contains(ts("2025-03-03T06:00:00Z"), UTC));
+    }
+
+    #[test]
+    fn reset_verdict_compares_the_earliest_exhaustion_with_the_reset() {
+        let reset = ts("2025-03-01T15:00:00Z");
+        let at = |rfc3339| Some(ts(rfc3339));
+
+        assert_eq!(
+            reset_verdict(at("2025-03-01T14:20:00Z"), at("2025-03-01T14:50:00Z"), reset),
+            ResetVerdict::WillExhaust { before_secs: 2_400 }
+        );
+        assert_eq!(reset_verdict(None, at("2025-03-01T14:59:00Z"), reset), ResetVerdict::WillExhaust { before_secs: 60 });
+        assert_eq!(reset_verdict(at("2025-03-01T15:00:00Z"), None, reset), ResetVerdict::WillSurvive);
+        assert_eq!(reset_verdict(at("2025-03-01T16:00:00Z"), at("2025-03-01T17:00:00Z"), reset), ResetVerdict::WillSurvive);
+        // No burn, no prediction
+        assert_eq!(reset_verdict(None, None, reset), ResetVerdict::WillSurvive);
+    }
+
+    #[test]
+    fn tied_timestamps_stay_in_one_block() {
+        let tie = |rfc3339| sonnet(ts(rfc3339));
+        let entries = [tie("2025-03-01T10:00:00Z"), tie("2025-03-01T10:00:00Z"), tie("2025-03-01T10:00:00Z")];
+        let blocks = create_blocks(&entries, &no_grace(), &LimitPolicy::default());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].entries.len(), 3);
+
+        // A tie exactly at the block end opens one new block, not two
+        let entries = [tie("2025-03-01T10:00:00Z"), tie("2025-03-01T15:00:00Z"), tie("2025-03-01T15:00:00Z")];
+        let blocks = create_blocks(&entries, &no_grace(), &LimitPolicy::default());
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].entries.len(), 2);
+    }
+
+    #[test]
+    fn unsorted_entries_are_grouped_like_sorted_ones() {
+        let sorted = [sonnet(ts("2025-03-01T10:00:00Z")), sonnet(ts("2025-03-01T12:00:00Z")), sonnet(ts("2025-03-01T18:00:00Z"))];
+        let unsorted = [sorted[2].clone(), sorted[0].clone(), sorted[1].clone()];
+        let a = create_blocks(&sorted, &no_grace(), &LimitPolicy::default());
+        let b = create_blocks(&unsorted, &no_grace(), &LimitPolicy::default());
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.len(), b.len());
+        for (x, y) in a.iter().zip(&b) {
+            assert_eq!(x.start_time, y.start_time);
+            assert_eq!(x.entries.len(), y.entries.len());
+        }
+    }
+
+    #[test]
+    fn daily_series_zero_fills_days_without_usage() {
+        let policy = LimitPolicy::default();
+        let today = local_day(Utc::now(), 0, UTC);
+        let noon = |back| sonnet((today - Duration::days(back)).and_hms_opt(12, 0, 0).unwrap().and_utc());
+        let entries = [noon(9), noon(4), noon(2), noon(2), noon(0)];
+
+        let series = daily_cost_series(&entries, 5, 0, UTC, &policy);
+        let days: Vec<NaiveDate> = series.iter().map(|&(day, _)| day).collect();
+        assert_eq!(days, (0..5).rev().map(|back| today - Duration::days(back)).collect::<Vec<_>>());
+        let millis: Vec<i64> = series.iter().map(|&(_, cost)| (cost * 1_000.0).round() as i64).collect();
+        assert_eq!(millis, [15, 0, 30, 0, 15]);
+
+        let empty = daily_cost_series(&[], 3, 0, UTC, &policy);
+        assert_eq!(empty.len(), 3);
+        assert!(empty.iter().all(|&(_, cost)| cost == 0.0));
+    }
+
+    fn costing(limit_cost: f64) -> PeriodStats {
+        PeriodStats { limit_cost, total_cost: limit_cost, ..Default::default() }
+    }
+
+    #[test]
+    fn trends_compare_periods_and_guard_empty_ones() {
+        // Day 10 of the month and day 3 of the week, both finished
+        let trends = compute_trends(&costing(6.0), &costing(12.0), &costing(40.0), (3.0, 10.0), (1.0, 1.0), true);
+        assert_eq!(trends.today_of_week_percent, 50.0);
+        assert_eq!(trends.week_of_month_percent, 30.0);
+        assert_eq!(trends.week_daily_avg, 4.0);
+        assert_eq!(trends.month_daily_avg, 4.0);
+        assert!(trends.accelerating);
+
+        let quiet = compute_trends(&costing(2.0), &costing(12.0), &costing(40.0), (3.0, 10.0), (1.0, 1.0), true);
+        assert!(!quiet.accelerating);
+
+        let empty = compute_trends(&costing(0.0), &costing(0.0), &costing(0.0), (0.0, 0.0), (0.0, 0.0), false);
+        assert_eq!((empty.today_of_week_percent, empty.week_of_month_percent), (0.0, 0.0));
+        assert_eq!(empty.month_daily_avg, 0.0);
+        assert!(!empty.accelerating);
+    }
+
+    #[test]
+    fn filters_follow_the_configured_zone() {
+        let kolkata = Zone::Named(chrono_tz::Asia::Kolkata);
+        let now = Utc::now();
+        let hourly: Vec<Entry> = (0..48).map(|back| sonnet(now - Duration::hours(back))).collect();
+
+        // One entry per elapsed hour of the local day, plus the current one
+        let today_utc = filter_today(&hourly, 0, UTC).len() as u32;
+        let today_kolkata = filter_today(&hourly, 0, kolkata).len() as u32;
+        assert_eq!(today_utc, UTC.naive(now).hour() + 1);
+        assert_eq!(today_kolkata, kolkata.naive(now).hour() + 1);
+        assert_ne!(today_utc, today_kolkata);
+
+        let evening = ts("2025-03-31T20:00:00Z");
+        assert_eq!(local_day(evening, 0, UTC), NaiveDate::from_ymd_opt(2025, 3, 31).unwrap());
+        assert_eq!(local_day(evening, 0, kolkata), NaiveDate::from_ymd_opt(2025, 4, 1).unwrap());
+        assert_eq!(this_month_start(evening, 0, UTC), ts("2025-03-01T00:00:00Z"));
+        assert_eq!(this_month_start(evening, 0, kolkata), ts("2025-03-31T18:30:00Z"));
+    }
+
+    #[test]
+    fn sessions_are_totalled_separately_most_expensive_first() {
+        let at = ts("2025-03-01T10:00:00Z");
+        let model = "claude-sonnet-4-20250514";
+        let entries = [
+            entry(at, model, "cheap", 1_000),
+            entry(at, model, "pricey", 5_000),
+            entry(at, model, "cheap", 1_000),
+            entry(at, model, UNKNOWN_SESSION, 500),
+        ];
+        let sessions = aggregate_by_session(&entries, &LimitPolicy::default());
+
+        let ids: Vec<&str> = sessions.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, ["pricey", "cheap", UNKNOWN_SESSION]);
+        let calls: Vec<u64> = sessions.iter().map(|(_, stats)| stats.total_calls).collect();
+        assert_eq!(calls, [1, 2, 1]);
+        assert_eq!(sessions[1].1.total_tokens, 2_200);
+    }
+
+    #[test]
+    fn invalid_utf8_or_truncated_lines_skip_only_themselves() {
+        let mut data = Vec::new();
+        data.extend_from_slice(br#"{"timestamp":"2025-03-01T10:00:00Z","message":{"model":"claude-sonnet-4","usage":{"output_tokens":1}}}"#);
+        data.extend_from_slice(b"\n{\"timestamp\":\"2025-03-01T10:01:00Z\",\"message\":{\"model\":\"\xff\xfe\"}}\n");
+        data.extend_from_slice(br#"{"timestamp":"2025-03-01T10:02:00Z","message":{"model":"claude-opus-4","usage":{"output_tokens":2}}}"#);
+        // Last line still being written
+        data.extend_from_slice(b"\n{\"timestamp\":\"2025-03-01T10:03:00Z\",\"mess");
+
+        let entries = parse_reader(data.as_slice());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].model, "claude-opus-4");
+        assert_eq!(entries[1].usage.output_tokens, 2);
+    }
+
+    #[test]
+    fn projections_extrapolate_from_the_elapsed_fraction() {
+        assert_eq!(project_period_cost(5.0, 0.25), 20.0);
+        assert_eq!(project_period_cost(5.0, 1.0), 5.0);
+        // Early in the period the fraction is clamped, no 50x projections
+        assert_eq!(project_period_cost(1.0, 0.02), 10.0);
+        assert_eq!(project_period_cost(1.0, 0.0), 10.0);
+
+        let (day, month) = elapsed_fractions(ts("2025-03-11T06:00:00Z"), 0, UTC);
+        assert_eq!(day, 0.25);
+        assert!((month - 10.25 / 31.0).abs() < 1e-12);
+        // Days starting at 04:00: 06:00 is 2 hours in
+        assert!((elapsed_fractions(ts("2025-03-11T06:00:00Z"), 4, UTC).0 - 2.0 / 24.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn future_entries_are_clamped_into_the_active_block() {
+        let now = Utc::now();
+        // A skewed entry counts as happening now
+        let mut entries = vec![sonnet(now + Duration::minutes(2))];
+        clamp_future_entries(&mut entries, now);
+        assert_eq!(entries[0].timestamp, now);
+        let blocks = create_blocks(&entries, &BlockSettings::default(), &LimitPolicy::default());
+        assert_eq!(find_current_block(&blocks).map(|b| b.entries.len()), Some(1));
+
+        let mut entries = vec![sonnet(ts("2025-03-01T11:00:00Z")), sonnet(ts("2025-03-01T12:02:00Z"))];
+        clamp_future_entries(&mut entries, ts("2025-03-01T12:00:00Z"));
+        assert_eq!(entries[0].timestamp, ts("2025-03-01T11:00:00Z"));
+        assert_eq!(entries[1].timestamp, ts("2025-03-01T12:00:00Z"));
+    }
+
+    fn mixed_tiers() -> Vec<Entry> {
+        let at = ts("2025-03-01T10:00:00Z");
+        vec![
+            entry(at, "claude-opus-4-20250514", "s1", 1_000),
+            entry(at, "claude-sonnet-4-20250514", "s1", 20_000),
+            entry(at, "claude-3-5-sonnet-20241022", "s1", 2_000),
+            entry(at, "claude-3-5-haiku-20241022", "s1", 5_000),
+        ]
+    }
+
+    #[test]
+    fn tier_totals_sum_to_the_period_cost() {
+        let stats = aggregate(&mixed_tiers(), "Today", &LimitPolicy::default());
+        let tiers = tier_breakdown(&stats, TierOrder::Family);
+
+        let names: Vec<&str> = tiers.iter().map(|t| t.tier.as_str()).collect();
+        assert_eq!(names, ["Opus", "Sonnet", "Haiku"]);
+        assert_eq!(tiers[1].calls, 2);
+        let total: f64 = tiers.iter().map(|t| t.cost).sum();
+        assert!((total - stats.total_cost).abs() < 1e-9);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_files_are_counted_not_fatal() {
+        let dir = fixture_dir("unreadable");
+        std::fs::create_dir_all(dir.join("work")).unwrap();
+        let line = jsonl_line(minutes_ago(30), "claude-sonnet-4-20250514", "s1", 1_000);
+        std::fs::write(dir.join("work").join("ok.jsonl"), line + "\n").unwrap();
+        // A symlink to itself fails to open with a non-transient error
+        let looped = dir.join("work").join("looped.jsonl");
+        std::os::unix::fs::symlink(&looped, &looped).unwrap();
+
+        let _lock = DATA_DIR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var(DATA_DIR_ENV, &dir);
+        let logs = parse_all_logs(&Config::default());
+        std::env::remove_var(DATA_DIR_ENV);
+        let vanished = parse_file(&dir.join("work").join("gone.jsonl"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let logs = logs.unwrap();
+        assert_eq!(logs.entries.len(), 1);
+        assert_eq!(logs.skipped_files, 1);
+        // A file that vanished is not an error
+        assert!(vanished.unwrap().is_empty());
+    }
+
+    #[test]
+    fn hourly_tokens_bucket_by_block_hour_so_far() {
+        let entries = [sonnet(ts("2025-03-01T10:05:00Z")), sonnet(ts("2025-03-01T10:55:00Z")), sonnet(ts("2025-03-01T12:10:00Z"))];
+        let block = &create_blocks(&entries, &no_grace(), &LimitPolicy::default())[0];
+
+        assert_eq!(block_hourly_tokens(block, ts("2025-03-01T12:30:00Z"), &LimitPolicy::default()), [2_000, 0, 1_000]);
+        // Hours not reached yet are left out, past the end there are five
+        assert_eq!(block_hourly_tokens(block, ts("2025-03-01T10:30:00Z"), &LimitPolicy::default()), [2_000]);
+        assert_eq!(block_hourly_tokens(block, ts("2025-03-01T18:00:00Z"), &LimitPolicy::default()).len(), 5);
+
+        let with_input = LimitPolicy { include_input: true, ..Default::default() };
+        assert_eq!(block_hourly_tokens(block, ts("2025-03-01T12:30:00Z"), &with_input), [2_200, 0, 1_100]);
+    }
+
+    #[test]
+    fn missing_and_empty_data_dirs_get_guidance() {
+        let dir = fixture_dir("empty-state");
+        let missing = dir.join("projects");
+
+        let _lock = DATA_DIR_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::env::set_var(DATA_DIR_ENV, &missing);
+        let before = parse_all_logs(&Config::default()).unwrap().empty_state();
+        std::fs::create_dir_all(&missing).unwrap();
+        let after = parse_all_logs(&Config::default()).unwrap().empty_state();
+        std::env::remove_var(DATA_DIR_ENV);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let before = before.unwrap();
+        assert!(before.missing);
+        assert_eq!(before.data_dir, missing.display().to_string());
+        assert!(!after.unwrap().missing);
+
+        let with_data = ParsedLogs { entries: vec![sonnet(minutes_ago(1))], data_dir: Some(missing), ..Default::default() };
+        assert!(with_data.empty_state().is_none());
+    }
+
+    #[test]
+    fn parse_reader_reads_a_piped_stream() {
+        let model = "claude-sonnet-4-20250514";
+        let stream = format!(
+            "{}\n\n{}\r\n",
+            jsonl_line(ts("2025-03-01T10:00:00Z"), model, "a", 10),
+            jsonl_line(ts("2025-03-01T11:00:00Z"), model, "b", 20)
+        );
+        let entries = parse_reader(stream.as_bytes());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].session_id, "b");
+        assert_eq!(entries[1].usage.output_tokens, 20);
+        assert!(parse_reader(&b""[..]).is_empty());
+    }
+
+    #[test]
+    fn plan_weeks_split_at_the_reset_anchor() {
+        let yesterday = Utc::now().date_naive() - Duration::days(1);
+        let reset = WeekReset { weekday: yesterday.weekday(), hour: 0, ..Default::default() };
+        let anchor = yesterday.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let entries = [
+            sonnet(anchor - Duration::days(7) - Duration::minutes(1)),
+            sonnet(anchor - Duration::days(7)),
+            sonnet(anchor - Duration::minutes(1)),
+            sonnet(anchor),
+            sonnet(anchor + Duration::minutes(1)),
+        ];
+        let times = |kept: Vec<Entry>| kept.iter().map(|e| e.timestamp).collect::<Vec<_>>();
+
+        assert_eq!(times(filter_plan_week(&entries, &reset, UTC)), [anchor, anchor + Duration::minutes(1)]);
+        assert_eq!(
+            times(filter_previous_plan_week(&entries, &reset, UTC)),
+            [anchor - Duration::days(7), anchor - Duration::minutes(1)]
+        );
+    }
+
+    #[test]
+    fn expired_blocks_leave_the_full_limit_available() {
+        let config = Config::default();
+        let entries = [sonnet(minutes_ago(20 * 60)), sonnet(minutes_ago(8 * 60))];
+        let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+        assert_eq!(blocks.len(), 2);
+
+        let info = get_current_block_info(&blocks, &PLANS[0], &config);
+        assert!(!info.is_active);
+        assert_eq!(info.limit_cost, 0.0);
+        assert_eq!(info.last_block_end, Some(blocks[1].end_time));
+        assert_eq!(last_block_end(&blocks), Some(blocks[1].end_time));
+
+        assert_eq!(get_current_block_info(&[], &PLANS[0], &config).last_block_end, None);
+    }
+
+    #[test]
+    fn synthetic_models_count_as_free_system_calls() {
+        let at = ts("2025-03-01T10:00:00Z");
+        let synthetic = r#"{"timestamp":"2025-03-01T10:00:00Z","costUSD":2.0,"message":{"model":"<synthetic>","usage":{"output_tokens":500}}}"#;
+        let mut entries = vec![map_str(synthetic).unwrap(), entry(at, "", "s1", 500), sonnet(at)];
+        assert_eq!(entries[0].model, SYSTEM_MODEL);
+        assert_eq!(entries[1].model, SYSTEM_MODEL);
+
+        let stats = aggregate(&entries, "Today", &LimitPolicy::default());
+        let real = aggregate(&entries[2..], "Today", &LimitPolicy::default());
+        assert_eq!(stats.total_calls, 3);
+        assert_eq!(stats.total_cost, real.total_cost);
+        assert_eq!(stats.limit_cost, real.limit_cost);
+
+        drop_system_entries(&mut entries);
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn last_week_is_the_previous_monday_to_sunday() {
+        let today = local_day(Utc::now(), 0, UTC);
+        let this_monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let at = |day: NaiveDate, hour| sonnet(day.and_hms_opt(hour, 0, 0).unwrap().and_utc());
+        let entries = [
+            at(this_monday - Duration::days(8), 23),
+            at(this_monday - Duration::days(7), 0),
+            at(this_monday - Duration::days(1), 23),
+            at(this_monday, 0),
+        ];
+
+        let last_week = filter_last_week(&entries, 0, UTC);
+        assert_eq!(last_week.len(), 2);
+        assert_eq!(last_week[0].timestamp, entries[1].timestamp);
+        assert_eq!(filter_this_week(&entries, 0, UTC).len(), 1);
+        // One side of the comparison can be empty
+        assert!(filter_last_week(&entries[3..], 0, UTC).is_empty());
+    }
+
+    #[test]
+    fn token_policy_picks_the_counted_categories_and_keeps_the_real_total() {
+        let cache_heavy = map_str(
+            r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"s1","message":{"model":"claude-sonnet-4","usage":{"input_tokens":100,"output_tokens":1000,"cache_creation_input_tokens":5000,"cache_read_input_tokens":200000}}}"#,
+        )
+        .unwrap();
+        let entries = [cache_heavy];
+        let limit_tokens = |include_input, include_cache_creation, include_cache_read| {
+            let policy = LimitPolicy { include_input, include_cache_creation, include_cache_read };
+            aggregate(&entries, "Today", &policy).limit_tokens
+        };
+
+        assert_eq!(limit_tokens(false, false, false), 1_000);
+        assert_eq!(limit_tokens(true, true, false), 6_100);
+        assert_eq!(limit_tokens(true, true, true), 206_100);
+        assert_eq!(aggregate(&entries, "Today", &LimitPolicy::default()).total_tokens, 206_100);
+    }
+
+    #[test]
+    fn sessionless_entries_take_their_file_as_session() {
+        let dir = fixture_dir("sessionless");
+        let line = r#"{"timestamp":"2025-03-01T10:00:00Z","message":{"model":"claude-sonnet-4","usage":{"output_tokens":10}}}"#;
+        let (first, second) = (dir.join("aaa.jsonl"), dir.join("bbb.jsonl"));
+        std::fs::write(&first, format!("{line}\n{line}\n")).unwrap();
+        std::fs::write(&second, format!("{line}\n")).unwrap();
+
+        let mut entries = parse_file(&first).unwrap();
+        entries.extend(parse_file(&second).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let sessions: Vec<&str> = entries.iter().map(|e| e.session_id.as_str()).collect();
+        assert_eq!(sessions, ["aaa", "aaa", "bbb"]);
+        assert_eq!(aggregate(&entries, "Today", &LimitPolicy::default()).session_count, 2);
+    }
+
+    #[test]
+    fn at_most_one_block_is_active_when_grace_overlaps_the_next() {
+        let now = Utc::now();
+        // Block A ends on an hour, busy up to its end; its grace runs just past now
+        let end = round_to_hour(now) - Duration::hours(5);
+        let grace = Duration::seconds((now - end).num_seconds() + 1);
+        let entries = [
+            sonnet(end - Duration::hours(5)),
+            sonnet(end - Duration::minutes(1)),
+            // Opens block B, whose start rounds down to the hour, inside A's grace
+            sonnet(end + grace),
+        ];
+        let settings = BlockSettings { grace_secs: grace.num_seconds(), exact_start: false };
+        let blocks = create_blocks(&entries, &settings, &LimitPolicy::default());
+
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].start_time <= now && blocks[1].start_time <= now);
+        assert!(!blocks[0].is_active);
+        assert!(blocks[1].is_active);
+        assert_eq!(find_current_block(&blocks).map(|b| b.start_time), Some(blocks[1].start_time));
+    }
+
+    #[test]
+    fn tier_order_is_stable_whatever_the_insertion_order() {
+        let forward = mixed_tiers();
+        let backward: Vec<Entry> = forward.iter().rev().cloned().collect();
+        let names = |entries: &[Entry], order| {
+            let stats = aggregate(entries, "Today", &LimitPolicy::default());
+            tier_breakdown(&stats, order).into_iter().map(|t| t.tier).collect::<Vec<_>>()
+        };
+
+        // Sonnet 22k output ($0.33) > Opus 1k ($0.075) > Haiku 5k ($0.02)
+        assert_eq!(names(&forward, TierOrder::Cost), ["Sonnet", "Opus", "Haiku"]);
+        assert_eq!(names(&backward, TierOrder::Cost), ["Sonnet", "Opus", "Haiku"]);
+        assert_eq!(names(&forward, TierOrder::Family), ["Opus", "Sonnet", "Haiku"]);
+        assert_eq!(names(&backward, TierOrder::Family), ["Opus", "Sonnet", "Haiku"]);
+    }
+
+    #[test]
+    fn billed_cost_covers_the_limit_cost_for_cache_heavy_months() {
+        let line = r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"s1","message":{"model":"claude-opus-4","usage":{"input_tokens":50,"output_tokens":2000,"cache_read_input_tokens":3000000}}}"#;
+        let entries = [map_str(line).unwrap(), sonnet(ts("2025-03-02T10:00:00Z"))];
+        let month = aggregate(&entries, "This Month", &LimitPolicy::default());
+        assert!(month.total_cost >= month.limit_cost);
+        // 3M cache reads at $1.50/M dominate the bill but not the limit
+        assert!(month.total_cost - month.limit_cost > 4.5);
+
+        let trends = compute_trends(&costing(0.0), &costing(0.0), &month, (1.0, 2.0), (0.5, 0.25), true);
+        assert!((trends.projected_month_bill - month.total_cost * 4.0).abs() < 1e-9);
+        assert!((trends.projected_end_of_month - month.limit_cost * 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ignored_names_are_skipped_while_scanning() {
+        let dir = fixture_dir("scan-ignore");
+        for path in ["work/a.jsonl", "work/a.bak.jsonl", "work/archive/old.jsonl", "archive.jsonl/x.jsonl", "notes.txt"] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let ignore = vec!["archive/".to_string(), "*.bak.jsonl".to_string()];
+        let mut found: Vec<String> = find_jsonl_files(&dir, &ignore)
+            .iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+            .collect();
+        found.sort();
+        let all = find_jsonl_files(&dir, &[]).len();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // "archive/" matches directory names by substring and is never descended into
+        assert_eq!(found, ["work/a.jsonl"]);
+        assert_eq!(all, 4);
+    }
+
+    #[test]
+    fn max_entry_cost_finds_the_costliest_call() {
+        let mut entries = mixed_tiers();
+        entries[2].timestamp = ts("2025-03-01T10:30:00Z");
+        entries[2].cost_usd = Some(2.5);
+
+        let (at, model, cost) = max_entry_cost(&entries).unwrap();
+        assert_eq!(at, ts("2025-03-01T10:30:00Z"));
+        assert_eq!(model, "claude-3-5-sonnet-20241022");
+        assert_eq!(cost, 2.5);
+        assert_eq!(max_entry_cost(&entries[..2]).unwrap().1, "claude-sonnet-4-20250514");
+        assert!(max_entry_cost(&[]).is_none());
+    }
+
+    #[test]
+    fn exact_start_moves_the_reset_to_the_first_entry_minute() {
+        let entries = [sonnet(ts("2025-03-01T10:17:42Z")), sonnet(ts("2025-03-01T12:00:00Z"))];
+        let reset = |exact_start| create_blocks(&entries, &BlockSettings { grace_secs: 0, exact_start }, &LimitPolicy::default())[0].end_time;
+        assert_eq!(reset(false), ts("2025-03-01T15:00:00Z"));
+        assert_eq!(reset(true), ts("2025-03-01T15:17:42Z"));
+
+        // The live block reports the same reset time
+        let first = minutes_ago(17);
+        let config = Config { blocks: BlockSettings { exact_start: true, ..Default::default() }, ..Default::default() };
+        let blocks = create_blocks(&[sonnet(first)], &config.blocks, &config.limit_policy);
+        assert_eq!(get_current_block_info(&blocks, &PLANS[0], &config).reset_time, Some(first + Duration::hours(5)));
+    }
+
+    #[test]
+    fn sonnet_versions_stay_separate_in_the_version_split() {
+        let at = minutes_ago(30);
+        let entries = [
+            entry(at, "claude-sonnet-4-20250514", "s1", 20_000),
+            entry(at, "claude-3-5-sonnet-20241022", "s1", 2_000),
+        ];
+        let policy = LimitPolicy::default();
+        let blocks = create_blocks(&entries, &no_grace(), &policy);
+
+        let versions = get_model_version_distribution(&blocks, &PLANS[0], &policy);
+        let names: Vec<&str> = versions.iter().map(|d| d.model.as_str()).collect();
+        assert_eq!(names, ["sonnet-4", "3-5-sonnet"]);
+        assert!(versions.iter().all(|d| d.tier == "Sonnet" && d.calls == 1));
+
+        let tiers = get_model_distribution(&blocks, &PLANS[0], &policy);
+        assert_eq!(tiers.len(), 1);
+        assert_eq!((tiers[0].model.as_str(), tiers[0].calls), ("Sonnet", 2));
+    }
+
+    #[test]
+    fn tier_limit_percents_sum_to_the_block_cost_percent() {
+        let at = minutes_ago(30);
+        let entries: Vec<Entry> = mixed_tiers().into_iter().map(|e| Entry { timestamp: at, ..e }).collect();
+        let config = Config::default();
+        let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+
+        let info = get_current_block_info(&blocks, &PLANS[0], &config);
+        let dist = get_model_distribution(&blocks, &PLANS[0], &config.limit_policy);
+        let total: f64 = dist.iter().map(|d| d.limit_percent).sum();
+        assert!(info.cost_percent > 0.0);
+        assert!((total - info.cost_percent).abs() < 1e-9);
+    }
+
+    #[test]
+    fn opus_cap_can_be_exceeded_under_the_general_cap() {
+        let at = minutes_ago(30);
+        let entries = [entry(at, "claude-opus-4-20250514", "s1", 1_000), entry(at, "claude-sonnet-4-20250514", "s1", 20_000)];
+        let config = Config::default();
+        let blocks = create_blocks(&entries, &config.blocks, &config.limit_policy);
+
+        let mut plan = PLANS[0].clone();
+        plan.tier_cost_limits.insert("Opus".into(), 0.05);
+        let info = get_current_block_info(&blocks, &plan, &config);
+        assert!(info.cost_percent < 100.0);
+        assert_eq!(info.tier_limits.len(), 1);
+        assert_eq!(info.tier_limits[0].tier, "Opus");
+        assert!(info.tier_limits[0].percent > 100.0);
+
+        // No per-tier caps: only the aggregate gauge
+        assert!(get_current_block_info(&blocks, &PLANS[0], &config).tier_limits.is_empty());
+    }
+
+    #[test]
+    fn equal_cost_models_sort_by_name() {
+        let at = ts("2025-03-01T10:00:00Z");
+        let a = entry(at, "claude-sonnet-4-20250514", "s1", 1_000);
+        let b = entry(at, "claude-3-5-sonnet-20241022", "s1", 1_000);
+
+        for entries in [[a.clone(), b.clone()], [b, a]] {
+            let stats = aggregate(&entries, "Today", &LimitPolicy::default());
+            assert_eq!(stats.models[0].cost, stats.models[1].cost);
+            let names: Vec<&str> = stats.models.iter().map(|m| m.model.as_str()).collect();
+            assert_eq!(names, ["claude-3-5-sonnet-20241022", "claude-sonnet-4-20250514"]);
+        }
+    }
+
+    #[test]
+    fn prorated_and_full_day_divisors_mid_day() {
+        // Wednesday 5 March, noon
+        let (week, month) = elapsed_days(ts("2025-03-05T12:00:00Z"), 0, UTC, None);
+        assert_eq!((week, month), (2.5, 4.5));
+        assert_eq!(average_days(week, false), 3.0);
+        assert_eq!(average_days(week, true), 2.5);
+        assert_eq!(average_days(month, false), 5.0);
+        assert_eq!(average_days(month, true), 4.5);
+
+        // Right after the day starts the pro-rated divisor never reaches zero
+        assert!(average_days(0.0, true) > 0.0);
+        assert_eq!(average_days(0.0, false), 1.0);
+    }
+
+    #[test]
+    fn garbage_huge_token_lines_are_clamped_without_panicking() {
+        let at = ts("2025-03-01T10:00:00Z");
+        let huge = jsonl_line(at, "claude-sonnet-4-20250514", "s1", i64::MAX as u64);
+        let jsonl = format!("{huge}\n{huge}\n{}\n", jsonl_line(at, "claude-sonnet-4-20250514", "s1", 1_000));
+        let mut entries = parse_reader(jsonl.as_bytes());
+        assert_eq!(entries.len(), 3);
+
+        // Sums saturate instead of overflowing
+        let stats = aggregate(&entries, "Today", &LimitPolicy::default());
+        assert_eq!(stats.total_tokens, u64::MAX);
+
+        clamp_huge_entries(&mut entries, 10_000_000);
+        assert_eq!(entries[0].usage.output_tokens, 10_000_000);
+        assert_eq!(entries[2].usage.output_tokens, 1_000);
+        let clamped = aggregate(&entries, "Today", &LimitPolicy::default());
+        assert_eq!(clamped.total_tokens, 2 * 10_000_000 + 1_000 + 3 * 100);
+        assert!(clamped.total_cost < stats.total_cost);
+    }
+
+    #[test]
+    fn cost_by_weekday_sums_known_days() {
+        // Saturday 1, Sunday 2 and Monday 3 March
+        let entries = [
+            sonnet(ts("2025-03-01T10:00:00Z")),
+            sonnet(ts("2025-03-02T02:00:00Z")),
+            sonnet(ts("2025-03-03T09:00:00Z")),
+            sonnet(ts("2025-03-03T15:00:00Z")),
+        ];
+        let policy = LimitPolicy::default();
+        let one = calculate_entry_limit_cost(&entries[0], &policy);
+
+        let costs = cost_by_weekday(&entries, 0, UTC, &policy);
+        assert_eq!(costs, [2.0 * one, 0.0, 0.0, 0.0, 0.0, one, one]);
+
+        // Sunday 02:00 counts as Saturday with a 04:00 day start
+        let costs = cost_by_weekday(&entries, 4, UTC, &policy);
+        assert_eq!((costs[5], costs[6]), (2.0 * one, 0.0));
+    }
+
+    #[test]
+    fn weekly_percentages_count_only_the_rolling_window() {
+        let day = 24 * 60;
+        let oldest_kept = minutes_ago(7 * day - 60);
+        let entries = [sonnet(minutes_ago(7 * day + 60)), sonnet(oldest_kept), sonnet(minutes_ago(60))];
+        let policy = LimitPolicy::default();
+        let one = calculate_entry_limit_cost(&entries[0], &policy);
+
+        let plan = PlanLimits { weekly_cost_limit: 8.0 * one, weekly_token_limit: 4_000, ..Default::default() };
+        let info = get_weekly_limit_info(&entries, &plan, &policy);
+        assert!(info.enabled);
+        assert_eq!(info.limit_tokens, 2_000);
+        assert_eq!(info.tokens_percent, 50.0);
+        assert!((info.cost_percent - 25.0).abs() < 1e-9);
+        assert_eq!(info.reset_estimate, Some(oldest_kept + Duration::days(7)));
+
+        // No weekly caps: panel disabled
+        assert!(!get_weekly_limit_info(&entries, &PlanLimits::default(), &policy).enabled);
+    }
+
+    #[test]
+    fn tier_fallbacks_count_downgrades_per_session() {
+        let at = |s: &str| ts(&format!("2025-03-01T10:{s}Z"));
+        let entries = [
+            entry(at("00:00"), "claude-opus-4", "a", 100),
+            entry(at("01:00"), "claude-sonnet-4", "a", 100),
+            // Upgrade back: not a fallback
+            entry(at("02:00"), "claude-opus-4", "a", 100),
+            // Other session: never compared with session a
+            entry(at("01:30"), "claude-3-5-haiku", "b", 100),
+        ];
+        let fallbacks = tier_fallbacks(&entries);
+        assert_eq!(fallbacks.fallback_count, 1);
+        assert_eq!(fallbacks.timestamps, [at("01:00")]);
+    }
+
+    #[test]
+    fn parallel_lower_tier_calls_are_not_fallbacks() {
+        let entries = [
+            entry(ts("2025-03-01T10:00:00Z"), "claude-opus-4", "a", 100),
+            entry(ts("2025-03-01T10:00:00.500Z"), "claude-3-5-haiku", "a", 100),
+            // Compared with the Haiku call just before, so no downgrade
+            entry(ts("2025-03-01T10:01:00Z"), "claude-3-5-haiku", "a", 100),
+        ];
+        assert_eq!(tier_fallbacks(&entries).fallback_count, 0);
+    }
+
+    #[test]
+    fn day_start_hour_shifts_month_start_and_daily_series() {
+        assert_eq!(this_month_start(ts("2025-03-15T12:00:00Z"), 0, UTC), ts("2025-03-01T00:00:00Z"));
+        // 02:00 on the 1st still belongs to February when days start at 04:00
+        assert_eq!(this_month_start(ts("2025-03-01T02:00:00Z"), 4, UTC), ts("2025-02-01T04:00:00Z"));
+
+        // A 2am entry lands in the previous day's bar
+        let today = local_day(Utc::now(), 4, UTC);
+        let entries = [sonnet(today.and_hms_opt(2, 0, 0).unwrap().and_utc())];
+        let policy = LimitPolicy::default();
+        let series = daily_cost_series(&entries, 3, 4, UTC, &policy);
+        assert_eq!(series[2].0, today);
+        assert_eq!(series[2].1, 0.0);
+        assert!(series[1].1 > 0.0);
     }
 }
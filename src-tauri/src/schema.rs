@@ -0,0 +1,165 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::models::{deserialize_timestamp, Entry, RawEntry, Usage};
+
+/// Maps one JSONL line of a given logging schema to an entry
+/// Adding a schema = one more implementation appended to SCHEMAS
+pub trait SchemaMapper: Sync {
+    /// None when the value isn't in this schema or carries no tokens
+    fn map_value(&self, value: &Value) -> Option<Entry>;
+
+    /// Map a raw line; the default goes through a `Value`, schemas that can
+    /// deserialize straight from the text override it
+    fn map_str(&self, line: &str) -> Option<Entry> {
+        self.map_value(&serde_json::from_str(line).ok()?)
+    }
+}
+
+/// Built-in schemas, tried in order for each line (first match wins)
+pub static SCHEMAS: &[&dyn SchemaMapper] = &[&ClaudeCodeSchema, &CamelCaseSchema];
+
+/// Map a line with the first schema that accepts it
+pub fn map_line(value: &Value) -> Option<Entry> {
+    SCHEMAS.iter().find_map(|schema| schema.map_value(value))
+}
+
+/// Map a raw JSONL line with the first schema that accepts it
+/// Claude Code comes first and reads the text directly, so most lines never build a `Value`
+pub fn map_str(line: &str) -> Option<Entry> {
+    SCHEMAS.iter().find_map(|schema| schema.map_str(line))
+}
+
+/// Claude Code logs: `message.{model, usage}` with top-level fallbacks
+/// (see RawEntry for the accepted variants)
+pub struct ClaudeCodeSchema;
+
+impl SchemaMapper for ClaudeCodeSchema {
+    fn map_value(&self, value: &Value) -> Option<Entry> {
+        RawEntry::deserialize(value).ok().and_then(|raw| Entry::try_from(raw).ok())
+    }
+
+    fn map_str(&self, line: &str) -> Option<Entry> {
+        serde_json::from_str::<RawEntry>(line).ok().and_then(|raw| Entry::try_from(raw).ok())
+    }
+}
+
+/// Wrappers logging camelCase usage keys next to the model:
+/// `{"timestamp", "session_id", "model", "usage": {"inputTokens", "outputTokens", ...}}`
+pub struct CamelCaseSchema;
+
+#[derive(Deserialize)]
+struct CamelEntry {
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(alias = "sessionId")]
+    session_id: Option<String>,
+    model: String,
+    usage: CamelUsage,
+    #[serde(rename = "costUSD", alias = "costUsd")]
+    cost_usd: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CamelUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
+    #[serde(default)]
+    cache_creation_input_tokens: u64,
+    #[serde(default)]
+    cache_read_input_tokens: u64,
+}
+
+impl SchemaMapper for CamelCaseSchema {
+    fn map_value(&self, value: &Value) -> Option<Entry> {
+        let camel = CamelEntry::deserialize(value).ok()?;
+        let usage = Usage {
+            input_tokens: camel.usage.input_tokens,
+            output_tokens: camel.usage.output_tokens,
+            cache_creation_input_tokens: camel.usage.cache_creation_input_tokens,
+            cache_read_input_tokens: camel.usage.cache_read_input_tokens,
+        };
+        // Same validation as Claude Code lines (system models, empty usage)
+        Entry::try_from(RawEntry {
+            timestamp: camel.timestamp,
+            session_id: camel.session_id,
+            message: None,
+            model: Some(camel.model),
+            usage: Some(usage),
+            cost_usd: camel.cost_usd,
+        })
+        .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAUDE_LINE: &str = r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"abc","message":{"model":"claude-sonnet-4","usage":{"input_tokens":10,"output_tokens":20,"cache_read_input_tokens":30}}}"#;
+    const CAMEL_LINE: &str = r#"{"timestamp":"2025-03-01T10:00:00Z","sessionId":"abc","model":"claude-sonnet-4","usage":{"inputTokens":10,"outputTokens":20,"cacheReadInputTokens":30}}"#;
+
+    fn assert_same(a: &Entry, b: &Entry) {
+        assert_eq!(a.timestamp, b.timestamp);
+        assert_eq!(a.session_id, b.session_id);
+        assert_eq!(a.model, b.model);
+        assert_eq!(a.usage.total(), b.usage.total());
+        assert_eq!(a.usage.cache_read_input_tokens, b.usage.cache_read_input_tokens);
+    }
+
+    #[test]
+    fn claude_code_lines_map() {
+        let value: Value = serde_json::from_str(CLAUDE_LINE).unwrap();
+        let entry = ClaudeCodeSchema.map_value(&value).unwrap();
+        assert_eq!(entry.session_id, "abc");
+        assert_eq!(entry.usage.total(), 60);
+        assert!(CamelCaseSchema.map_value(&value).is_none());
+    }
+
+    #[test]
+    fn camel_case_lines_map_like_claude_code_ones() {
+        let value: Value = serde_json::from_str(CAMEL_LINE).unwrap();
+        assert!(ClaudeCodeSchema.map_value(&value).is_none());
+        let camel = CamelCaseSchema.map_value(&value).unwrap();
+        assert_same(&camel, &map_str(CLAUDE_LINE).unwrap());
+    }
+
+    #[test]
+    fn every_schema_maps_text_like_values() {
+        for schema in SCHEMAS {
+            for line in [CLAUDE_LINE, CAMEL_LINE] {
+                let value: Value = serde_json::from_str(line).unwrap();
+                match (schema.map_str(line), schema.map_value(&value)) {
+                    (Some(a), Some(b)) => assert_same(&a, &b),
+                    (a, b) => assert_eq!(a.is_some(), b.is_some()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn map_str_agrees_with_map_line() {
+        for line in [CLAUDE_LINE, CAMEL_LINE] {
+            let value: Value = serde_json::from_str(line).unwrap();
+            assert_same(&map_str(line).unwrap(), &map_line(&value).unwrap());
+        }
+    }
+
+    #[test]
+    fn lines_without_usage_map_to_nothing() {
+        assert!(map_str(r#"{"timestamp":"2025-03-01T10:00:00Z","type":"user","message":{"role":"user"}}"#).is_none());
+        assert!(map_str(r#"{"timestamp":"2025-03-01T10:00:00Z","model":"x","usage":{"inputTokens":0}}"#).is_none());
+        assert!(map_str("not json").is_none());
+    }
+
+    #[test]
+    fn logged_cost_is_read_when_present() {
+        let with_cost = CLAUDE_LINE.replacen(r#""sessionId""#, r#""costUSD":0.42,"sessionId""#, 1);
+        assert_eq!(map_str(&with_cost).unwrap().cost_usd, Some(0.42));
+        assert_eq!(map_line(&serde_json::from_str(&with_cost).unwrap()).unwrap().cost_usd, Some(0.42));
+        assert_eq!(map_str(CLAUDE_LINE).unwrap().cost_usd, None);
+    }
+}
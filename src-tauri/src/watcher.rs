@@ -0,0 +1,73 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Flags changes to JSONL logs so the UI refreshes only when usage moves
+pub struct LogWatcher {
+    dirty: Arc<AtomicBool>,
+    /// Dropping the watcher stops the notifications
+    #[cfg(feature = "watch")]
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl LogWatcher {
+    /// Watch `path` (a directory recursively, or one file)
+    /// None when the platform watcher can't start; callers fall back to polling
+    #[cfg(feature = "watch")]
+    pub fn start(path: &Path) -> Option<Self> {
+        use notify::{RecursiveMode, Watcher};
+
+        let dirty = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&dirty);
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if !event.kind.is_access() {
+                    mark_if_jsonl(&event.paths, &flag);
+                }
+            }
+        })
+        .map_err(|e| eprintln!("File watcher unavailable, polling instead: {}", e))
+        .ok()?;
+
+        let mode = if path.is_dir() { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher
+            .watch(path, mode)
+            .map_err(|e| eprintln!("Cannot watch {}, polling instead: {}", path.display(), e))
+            .ok()?;
+
+        Some(Self { dirty, _watcher: watcher })
+    }
+
+    /// Built without the "watch" feature: always poll
+    #[cfg(not(feature = "watch"))]
+    pub fn start(_path: &Path) -> Option<Self> {
+        None
+    }
+
+    /// Did a log change since the last call? Clears the flag
+    pub fn take_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Set `dirty` when any of the changed paths is a .jsonl file
+pub fn mark_if_jsonl(paths: &[PathBuf], dirty: &AtomicBool) {
+    if paths.iter().any(|p| p.extension().is_some_and(|e| e == "jsonl")) {
+        dirty.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jsonl_changes_set_the_dirty_flag() {
+        let dirty = AtomicBool::new(false);
+        mark_if_jsonl(&[PathBuf::from("/p/notes.txt"), PathBuf::from("/p/work")], &dirty);
+        assert!(!dirty.load(Ordering::Relaxed));
+
+        mark_if_jsonl(&[PathBuf::from("/p/notes.txt"), PathBuf::from("/p/work/session.jsonl")], &dirty);
+        assert!(dirty.load(Ordering::Relaxed));
+    }
+}
@@ -1,6 +1,6 @@
-import { useEffect, useState, useCallback, useRef } from "react";
+import { createContext, useContext, useEffect, useMemo, useState, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { DashboardData, PlanLimits, ModelDistribution } from "./types";
+import type { BlockSummary, DashboardData, PlanLimits, ModelDistribution, PeriodStats } from "./types";
 import { themes, themeKeys, applyTheme, getStoredTheme, storeTheme } from "./themes";
 
 // Settings types
@@ -8,16 +8,24 @@ interface AppSettings {
   refreshInterval: number; // in seconds, 0 = disabled
   autoRefresh: boolean;
   animations: boolean;
-  defaultPlanIndex: number;
+  costMode: CostMode;
+  redactModels: boolean;
 }
 
+// "limit" = what counts towards the rate limit, "real" = billed usage incl. cache
+type CostMode = "limit" | "real";
+
 const DEFAULT_SETTINGS: AppSettings = {
   refreshInterval: 60, // 1 minute default
   autoRefresh: true,
   animations: true,
-  defaultPlanIndex: 1, // Max5
+  costMode: "limit",
+  redactModels: false,
 };
 
+// Plan shown until the config or usage history says otherwise (Max5)
+const DEFAULT_PLAN_INDEX = 1;
+
 const REFRESH_OPTIONS = [
   { value: 30, label: "30s" },
   { value: 60, label: "1 min" },
@@ -47,19 +55,6 @@ const storeSettings = (settings: AppSettings): void => {
   }
 };
 
-// Format helpers
-const formatTokens = (count: number): string => {
-  if (count >= 1_000_000) return `${(count / 1_000_000).toFixed(1)}M`;
-  if (count >= 1_000) return `${(count / 1_000).toFixed(1)}K`;
-  return count.toString();
-};
-
-const formatCost = (cost: number): string => {
-  if (cost >= 100) return `$${cost.toFixed(0)}`;
-  if (cost >= 10) return `$${cost.toFixed(1)}`;
-  return `$${cost.toFixed(2)}`;
-};
-
 const formatDuration = (secs: number): string => {
   if (secs <= 0) return "now";
   const hours = Math.floor(secs / 3600);
@@ -70,35 +65,136 @@ const formatDuration = (secs: number): string => {
   return `${s}s`;
 };
 
-const formatTime = (isoString: string | null): string => {
-  if (!isoString) return "N/A";
-  const date = new Date(isoString);
-  return date.toLocaleTimeString("fr-FR", { hour: "2-digit", minute: "2-digit" });
+// Display options from the backend config (costs arrive in USD)
+type DisplayOptions = Pick<DashboardData, "token_format" | "ascii" | "credits" | "currency" | "timezone">;
+
+const DEFAULT_DISPLAY: DisplayOptions = {
+  token_format: { decimals: 1, raw: false },
+  ascii: false,
+  credits: null,
+  currency: { symbol: "$", rate: 1, decimal_separator: "." },
+  timezone: null,
 };
 
+// Format helpers for one set of display options
+const makeFormat = ({ token_format, ascii, credits, currency, timezone }: DisplayOptions) => {
+  // Configured IANA timezone (undefined = system local)
+  const timeZone = timezone ?? undefined;
+
+  const formatTokens = (count: number): string => {
+    const { decimals, raw } = token_format;
+    if (raw) return Math.round(count).toLocaleString("en-US");
+    if (count >= 1_000_000_000) return `${(count / 1_000_000_000).toFixed(decimals)}B`;
+    if (count >= 1_000_000) return `${(count / 1_000_000).toFixed(decimals)}M`;
+    if (count >= 1_000) return `${(count / 1_000).toFixed(decimals)}K`;
+    return count.toString();
+  };
+
+  // ASCII markers instead of emoji (backend config / --ascii)
+  const ic = (emoji: string, asciiMarker: string): string => (ascii ? asciiMarker : emoji);
+
+  // Convert a USD cost and format it with the configured symbol and separators
+  const formatMoney = (usd: number, precise = false): string => {
+    const amount = usd * currency.rate;
+    const sign = amount < 0 ? "-" : "";
+    const abs = Math.abs(amount);
+    let digits: string;
+    if (precise) {
+      const [int, frac] = abs.toFixed(2).split(".");
+      const thousands = currency.decimal_separator === "," ? "." : ",";
+      digits = `${int.replace(/\B(?=(\d{3})+(?!\d))/g, thousands)}${currency.decimal_separator}${frac}`;
+    } else {
+      digits = abs.toFixed(abs >= 100 ? 0 : abs >= 10 ? 1 : 2).replace(".", currency.decimal_separator);
+    }
+    return `${sign}${currency.symbol}${digits}`;
+  };
+
+  // Credits (when configured) instead of money
+  const formatCost = (cost: number): string => {
+    if (credits) return `${Math.round(cost * credits.per_dollar)} ${credits.unit}`;
+    return formatMoney(cost);
+  };
+
+  // Always two decimals with separators, for totals reconciled against invoices
+  const formatCostPrecise = (cost: number): string => {
+    if (credits) return formatCost(cost);
+    return formatMoney(cost, true);
+  };
+
+  const formatTime = (isoString: string | null): string => {
+    if (!isoString) return "N/A";
+    const date = new Date(isoString);
+    return date.toLocaleTimeString("fr-FR", { hour: "2-digit", minute: "2-digit", timeZone });
+  };
+
+  const formatDayTime = (isoString: string | null): string => {
+    if (!isoString) return "N/A";
+    const date = new Date(isoString);
+    return date.toLocaleString("fr-FR", { weekday: "short", hour: "2-digit", minute: "2-digit", timeZone });
+  };
+
+  const formatDateTime = (isoString: string): string =>
+    new Date(isoString).toLocaleString("fr-FR", {
+      day: "2-digit",
+      month: "2-digit",
+      hour: "2-digit",
+      minute: "2-digit",
+      timeZone,
+    });
+
+  const formatDay = (isoString: string): string =>
+    new Date(isoString).toLocaleDateString("fr-FR", { weekday: "short", day: "numeric", timeZone });
+
+  const formatDate = (isoString: string): string => new Date(isoString).toLocaleDateString("fr-FR", { timeZone });
+
+  return { formatTokens, ic, formatCost, formatCostPrecise, formatTime, formatDayTime, formatDateTime, formatDay, formatDate };
+};
+
+type Format = ReturnType<typeof makeFormat>;
+
+// Formatters for the loaded config, provided by App
+const FormatContext = createContext<Format>(makeFormat(DEFAULT_DISPLAY));
+const useFormat = (): Format => useContext(FormatContext);
+
 const getTierBadge = (tier: string) => {
   if (tier === "Opus") return { name: "Opus", class: "badge-opus" };
   if (tier === "Haiku") return { name: "Haiku", class: "badge-haiku" };
+  if (tier === "Unknown") return { name: "Unknown", class: "badge-unknown" };
+  if (tier === "System") return { name: "System", class: "badge-unknown" };
   return { name: "Sonnet", class: "badge-sonnet" };
 };
 
 // Progress Bar component
+// Usage color by threshold: green < 80% <= yellow < 100% <= red
+const usageLevel = (percent: number): "success" | "warning" | "error" => {
+  if (percent >= 100) return "error";
+  if (percent >= 80) return "warning";
+  return "success";
+};
+
+const LEVEL_TEXT = { success: "text-success", warning: "text-warning", error: "text-error" };
+
 const ProgressBar = ({
   value,
   max,
   label,
   accentClass,
   showValues,
+  softMax,
 }: {
   value: number;
   max: number;
   label: string;
   accentClass: string;
   showValues?: boolean;
+  softMax?: number | null;
 }) => {
+  const { formatTokens } = useFormat();
   const percent = max > 0 ? (value / max) * 100 : 0;
   const isOverflow = percent > 100;
   const displayPercent = Math.min(percent, 100);
+  // A soft limit (internal budget) drives the color; the plan cap stays the denominator
+  const level = usageLevel(softMax ? (value / softMax) * 100 : percent);
 
   return (
     <div className="space-y-1">
@@ -110,14 +206,14 @@ const ProgressBar = ({
               {typeof value === "number" && value < 1000 ? value : formatTokens(value)} / {typeof max === "number" && max < 1000 ? max : formatTokens(max)}
             </span>
           )}
-          <span className={`font-mono font-bold ${isOverflow ? "text-error" : "text-accent-1"}`}>
+          <span className={`font-mono font-bold ${LEVEL_TEXT[level]}`}>
             {percent.toFixed(1)}%
           </span>
         </div>
       </div>
       <div className="progress-bar">
         <div
-          className={`progress-fill ${accentClass} ${isOverflow ? "animate-pulse" : ""}`}
+          className={`progress-fill ${level === "success" ? accentClass : level} ${isOverflow ? "animate-pulse" : ""}`}
           style={{ width: `${displayPercent}%` }}
         />
       </div>
@@ -146,6 +242,53 @@ const MainStat = ({
   </div>
 );
 
+// Index of the pinned block among past blocks (newest first); a block that fell out of them clamps to the oldest
+const pinnedBlockIndex = (pastBlocks: BlockSummary[], start: string | null): number | null => {
+  if (start === null || pastBlocks.length === 0) return null;
+  const index = pastBlocks.findIndex((b) => b.start_time === start);
+  return index >= 0 ? index : pastBlocks.length - 1;
+};
+
+// A past block pinned from the history ([ / ] to step, l for live)
+const PinnedBlockCard = ({ block, plan, onLive }: { block: BlockSummary; plan: PlanLimits; onLive: () => void }) => {
+  const { ic, formatCost, formatTokens, formatTime, formatDayTime } = useFormat();
+  return (
+    <section className="card glow">
+      <div className="flex items-center justify-between mb-6">
+        <div>
+          <h2 className="text-lg font-bold flex items-center gap-2">
+            Past Block
+            <span className="badge badge-opus text-xs">{ic("📌", "*")} pinned</span>
+          </h2>
+          <span className="text-xs text-secondary">
+            {formatDayTime(block.start_time)} → {formatTime(block.end_time)}
+          </span>
+        </div>
+        <button onClick={onLive} className="theme-btn px-2 py-1 text-xs" title="Back to the live block (l)">
+          Live
+        </button>
+      </div>
+      <div className="grid grid-cols-3 gap-6 mb-6">
+        <MainStat icon={ic("💰", "$")} label="Cost (limit)" value={formatCost(block.limit_cost)} max={formatCost(plan.cost_limit)} />
+        <MainStat icon={ic("🎯", "#")} label="Tokens (limit)" value={formatTokens(block.limit_tokens)} max={formatTokens(plan.token_limit)} />
+        <MainStat
+          icon={ic("💬", ">")}
+          label="Messages"
+          value={block.messages.toString()}
+          max={plan.message_limit > 0 ? plan.message_limit.toString() : "∞"}
+        />
+      </div>
+      <div className="space-y-3">
+        <ProgressBar value={block.limit_cost} max={plan.cost_limit} accentClass="accent-1" label="Cost" />
+        <ProgressBar value={block.limit_tokens} max={plan.token_limit} accentClass="accent-2" label="Tokens" />
+        {plan.message_limit > 0 && (
+          <ProgressBar value={block.messages} max={plan.message_limit} accentClass="accent-3" label="Messages" />
+        )}
+      </div>
+    </section>
+  );
+};
+
 // Mini Stat component for secondary info
 const MiniStat = ({ label, value, color }: { label: string; value: string; color?: string }) => (
   <div className="flex justify-between items-center py-1">
@@ -154,6 +297,44 @@ const MiniStat = ({ label, value, color }: { label: string; value: string; color
   </div>
 );
 
+// Percent change from `prev` to `cur` ("new" when prev is zero but cur isn't)
+const formatDeltaPercent = (cur: number, prev: number): string => {
+  if (prev === 0) return cur === 0 ? "0%" : "new";
+  const pct = ((cur - prev) / prev) * 100;
+  return `${pct >= 0 ? "+" : ""}${pct.toFixed(0)}%`;
+};
+
+// Current vs previous period with absolute and percent deltas
+const CompareTable = ({ cur, prev, limitMode }: { cur: PeriodStats; prev: PeriodStats; limitMode: boolean }) => {
+  const { formatCost, formatTokens } = useFormat();
+  const rows: [string, number, number, (n: number) => string][] = [
+    ["Cost", limitMode ? cur.limit_cost : cur.total_cost, limitMode ? prev.limit_cost : prev.total_cost, formatCost],
+    ["Tokens", limitMode ? cur.limit_tokens : cur.total_tokens, limitMode ? prev.limit_tokens : prev.total_tokens, formatTokens],
+    ["Calls", cur.total_calls, prev.total_calls, (n) => n.toString()],
+  ];
+  return (
+    <div className="card">
+      <div className="grid grid-cols-4 gap-2 text-xs font-mono">
+        <span />
+        <span className="text-secondary text-right">{cur.period_label}</span>
+        <span className="text-secondary text-right">{prev.period_label}</span>
+        <span className="text-secondary text-right">Δ</span>
+        {rows.map(([label, a, b, fmt]) => (
+          <div key={label} className="contents">
+            <span className="text-secondary">{label}</span>
+            <span className="text-right text-primary">{fmt(a)}</span>
+            <span className="text-right text-primary">{fmt(b)}</span>
+            <span className={`text-right ${a > b ? "text-warning" : "text-success"}`}>
+              {a >= b ? "+" : "-"}
+              {fmt(Math.abs(a - b))} ({formatDeltaPercent(a, b)})
+            </span>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+};
+
 // Small Card for secondary metrics
 const InfoCard = ({
   title,
@@ -172,12 +353,43 @@ const InfoCard = ({
   </div>
 );
 
+// Fixed-width input:output:cache_read split (empty when the model has no tokens)
+const TokenSplitBar = ({ input, output, cacheRead }: { input: number; output: number; cacheRead: number }) => {
+  const { formatTokens } = useFormat();
+  const total = input + output + cacheRead;
+  const pct = (n: number) => (total > 0 ? (n / total) * 100 : 0);
+  return (
+    <div
+      className="flex w-16 h-1.5 rounded-full overflow-hidden bg-secondary"
+      title={`in ${formatTokens(input)} • out ${formatTokens(output)} • cache read ${formatTokens(cacheRead)}`}
+    >
+      <div className="bg-accent-2" style={{ width: `${pct(input)}%` }} />
+      <div className="bg-accent-1" style={{ width: `${pct(output)}%` }} />
+      <div className="bg-accent-3" style={{ width: `${pct(cacheRead)}%` }} />
+    </div>
+  );
+};
+
+// Stable per-session aliases ("Sonnet A", "Opus B") for screenshots; stats keep real names
+const modelAliases = new Map<string, string>();
+const aliasModel = (model: string, tier: string): string => {
+  let alias = modelAliases.get(model);
+  if (!alias) {
+    const taken = [...modelAliases.values()].filter((a) => a.startsWith(`${tier} `)).length;
+    alias = `${tier} ${String.fromCharCode(65 + (taken % 26))}${taken >= 26 ? Math.floor(taken / 26) + 1 : ""}`;
+    modelAliases.set(model, alias);
+  }
+  return alias;
+};
+
 // Model Distribution Bar
-const ModelDistBar = ({ dist }: { dist: ModelDistribution }) => {
+const ModelDistBar = ({ dist, label }: { dist: ModelDistribution; label?: string }) => {
+  const { formatCost } = useFormat();
   const tier = getTierBadge(dist.tier);
   return (
     <div className="flex items-center gap-2 py-1">
       <span className={`badge ${tier.class} w-14 text-center text-xs`}>{tier.name}</span>
+      {label && <span className="font-mono text-xs text-primary truncate max-w-[96px]" title={label}>{label}</span>}
       <div className="flex-1 progress-bar h-1.5">
         <div
           className="progress-fill accent-2"
@@ -185,6 +397,127 @@ const ModelDistBar = ({ dist }: { dist: ModelDistribution }) => {
         />
       </div>
       <span className="font-mono text-xs text-accent-1 w-12 text-right">{formatCost(dist.cost)}</span>
+      <span className="font-mono text-xs text-secondary w-10 text-right" title="Share of the plan's cost limit">
+        {dist.limit_percent.toFixed(0)}%
+      </span>
+    </div>
+  );
+};
+
+// Cost-per-block Sparkline (values in cents, oldest first)
+const Sparkline = ({ values }: { values: number[] }) => {
+  const { formatCost } = useFormat();
+  if (values.length === 0) {
+    return <div className="text-xs text-secondary">No block history yet</div>;
+  }
+  const max = Math.max(...values, 1);
+  return (
+    <div className="flex items-end gap-1 h-12">
+      {values.map((cents, i) => (
+        <div
+          key={i}
+          className="flex-1 bg-accent-1 rounded-sm opacity-80"
+          style={{ height: `${Math.max((cents / max) * 100, 4)}%` }}
+          title={formatCost(cents / 100)}
+        />
+      ))}
+    </div>
+  );
+};
+
+// Tokens per elapsed hour of the current block (oldest first)
+const HourlyBars = ({ values }: { values: number[] }) => {
+  const { formatTokens } = useFormat();
+  const max = Math.max(...values, 1);
+  return (
+    <div className="flex items-end gap-2 h-12">
+      {values.map((tokens, i) => (
+        <div key={i} className="flex-1 flex flex-col items-center justify-end h-full">
+          <div
+            className="w-full bg-accent-2 rounded-sm opacity-80"
+            style={{ height: `${Math.max((tokens / max) * 100, 4)}%` }}
+            title={`Hour ${i + 1}: ${formatTokens(tokens)}`}
+          />
+          <span className="text-[10px] text-secondary">h{i + 1}</span>
+        </div>
+      ))}
+    </div>
+  );
+};
+
+// Below this window size the layout collapses to the current block only
+const COMPACT_WIDTH = 480;
+const COMPACT_HEIGHT = 420;
+
+// Data counts as stale after missing this many refresh intervals (plus slack)
+const STALE_INTERVALS = 2;
+const STALE_SLACK_SECS = 30;
+
+// Days shown in the daily spend chart
+const DAILY_DAYS = 30;
+
+// How often the backend log watcher is asked for changes (cheap: no parsing)
+const WATCH_POLL_MS = 1000;
+
+// Daily spend bars, oldest first ([ISO date, dollars])
+const DailyChart = ({ series }: { series: [string, number][] }) => {
+  const { formatCost } = useFormat();
+  const max = Math.max(...series.map(([, cost]) => cost), 0.01);
+  return (
+    <div className="flex items-end gap-[2px] h-16">
+      {series.map(([day, cost]) => (
+        <div
+          key={day}
+          className="flex-1 bg-accent-2 rounded-sm opacity-80"
+          style={{ height: `${Math.max((cost / max) * 100, 2)}%` }}
+          title={`${day}: ${formatCost(cost)}`}
+        />
+      ))}
+    </div>
+  );
+};
+
+// Usage Heatmap (rows = Mon..Sun, columns = hours, values in cents)
+const WEEKDAYS = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+// Cost per weekday (Mon..Sun), one labelled bar each
+const WeekdayChart = ({ costs }: { costs: number[] }) => {
+  const { formatCost } = useFormat();
+  const max = Math.max(...costs, 0.01);
+  return (
+    <div className="flex items-end gap-2 h-20">
+      {costs.map((cost, day) => (
+        <div key={day} className="flex-1 flex flex-col items-center justify-end h-full">
+          <div
+            className="w-full bg-accent-1 rounded-sm opacity-80"
+            style={{ height: `${Math.max((cost / max) * 100, 2)}%` }}
+            title={`${WEEKDAYS[day]}: ${formatCost(cost)}`}
+          />
+          <span className="text-[10px] text-secondary mt-1">{WEEKDAYS[day]}</span>
+        </div>
+      ))}
+    </div>
+  );
+};
+
+const Heatmap = ({ grid }: { grid: number[][] }) => {
+  const { formatCost } = useFormat();
+  const max = Math.max(...grid.flat(), 1);
+  return (
+    <div className="space-y-[2px]">
+      {grid.map((hours, day) => (
+        <div key={day} className="flex items-center gap-[2px]">
+          <span className="text-[10px] text-secondary w-8">{WEEKDAYS[day]}</span>
+          {hours.map((cents, hour) => (
+            <div
+              key={hour}
+              className="flex-1 h-3 rounded-sm bg-accent-1"
+              style={{ opacity: cents > 0 ? 0.15 + (cents / max) * 0.85 : 0.05 }}
+              title={`${WEEKDAYS[day]} ${hour}h: ${formatCost(cents / 100)}`}
+            />
+          ))}
+        </div>
+      ))}
     </div>
   );
 };
@@ -273,6 +606,46 @@ const LoadingOverlay = ({ isVisible, animations }: { isVisible: boolean; animati
   );
 };
 
+// Keyboard shortcuts listed in the help overlay
+const SHORTCUTS: [string, string][] = [
+  ["r", "Refresh now"],
+  ["c", "Toggle limit / real cost"],
+  ["v", "Compare with the previous day and week"],
+  ["g", "Group block models by tier / version"],
+  ["[ / ]", "Pin an older / newer past block"],
+  ["l", "Back to the live block"],
+  ["h", "Show / hide the cost-per-block sparkline"],
+  ["m", "Toggle the compact layout"],
+  ["y", "Copy the block reset time"],
+  ["e", "Copy the block history as CSV"],
+  ["+ / -", "Raise / lower the custom plan's limits by 10%"],
+  ["a", "Redact model names"],
+  ["/", "Filter the model table"],
+  ["?", "Show / hide this help"],
+  ["Esc", "Close this help"],
+];
+
+// Keyboard help overlay (toggle: ?)
+const HelpOverlay = ({ isOpen, onClose }: { isOpen: boolean; onClose: () => void }) => {
+  if (!isOpen) return null;
+  return (
+    <>
+      <div className="fixed inset-0 bg-black/40 z-40" onClick={onClose} />
+      <div className="fixed top-1/2 left-1/2 -translate-x-1/2 -translate-y-1/2 z-50 card p-6 min-w-[320px] shadow-2xl">
+        <h2 className="text-lg font-bold mb-4">Keyboard Shortcuts</h2>
+        <div className="space-y-1">
+          {SHORTCUTS.map(([key, action]) => (
+            <div key={key} className="flex items-center gap-4 text-sm">
+              <kbd className="font-mono text-accent-1 w-10 text-center bg-secondary rounded px-1">{key}</kbd>
+              <span className="text-secondary">{action}</span>
+            </div>
+          ))}
+        </div>
+      </div>
+    </>
+  );
+};
+
 // Settings Panel component
 const SettingsPanel = ({
   settings,
@@ -280,12 +653,16 @@ const SettingsPanel = ({
   isOpen,
   onClose,
   plans,
+  planIndex,
+  onSelectPlan,
 }: {
   settings: AppSettings;
   onSettingsChange: (settings: AppSettings) => void;
   isOpen: boolean;
   onClose: () => void;
   plans: PlanLimits[];
+  planIndex: number;
+  onSelectPlan: (index: number) => void;
 }) => {
   if (!isOpen) return null;
 
@@ -365,12 +742,33 @@ const SettingsPanel = ({
             </button>
           </div>
 
+          {/* Redact Model Names Toggle */}
+          <div className="flex items-center justify-between">
+            <div>
+              <div className="text-sm font-medium">Redact models</div>
+              <div className="text-xs text-secondary">Show aliases instead of model names (a)</div>
+            </div>
+            <button
+              onClick={() => updateSetting("redactModels", !settings.redactModels)}
+              className={`w-12 h-6 rounded-full transition-colors relative ${
+                settings.redactModels ? "bg-accent-1" : "bg-secondary"
+              }`}
+            >
+              <div
+                className={`w-5 h-5 rounded-full bg-white absolute top-0.5 transition-transform ${
+                  settings.redactModels ? "translate-x-6" : "translate-x-0.5"
+                }`}
+              />
+            </button>
+          </div>
+
           {/* Default Plan */}
           <div>
-            <div className="text-sm font-medium mb-2">Default Plan</div>
+            <div className="text-sm font-medium mb-2">Plan</div>
+            <div className="text-xs text-secondary mb-2">Saved to dashboard.toml for the next launch</div>
             <select
-              value={settings.defaultPlanIndex}
-              onChange={(e) => updateSetting("defaultPlanIndex", Number(e.target.value))}
+              value={planIndex}
+              onChange={(e) => onSelectPlan(Number(e.target.value))}
               className="w-full bg-secondary border border-white/10 rounded-lg px-3 py-2 text-sm focus:outline-none focus:border-accent-1"
             >
               {plans.map((plan, i) => (
@@ -392,7 +790,7 @@ function App() {
   const [data, setData] = useState<DashboardData | null>(null);
   const [plans, setPlans] = useState<PlanLimits[]>([]);
   const [settings, setSettings] = useState<AppSettings>(getStoredSettings());
-  const [planIndex, setPlanIndex] = useState(settings.defaultPlanIndex);
+  const [planIndex, setPlanIndex] = useState(DEFAULT_PLAN_INDEX);
   const [error, setError] = useState<string | null>(null);
   const [countdown, setCountdown] = useState(0);
   const [currentTheme, setCurrentTheme] = useState(getStoredTheme());
@@ -400,7 +798,29 @@ function App() {
   const [settingsOpen, setSettingsOpen] = useState(false);
   const [isLoading, setIsLoading] = useState(false);
   const [lastRefresh, setLastRefresh] = useState<Date | null>(null);
+  const [clock, setClock] = useState(Date.now());
+  const [showSparkline, setShowSparkline] = useState(true);
+  const [showCompare, setShowCompare] = useState(false);
+  const [showVersions, setShowVersions] = useState(false);
+  // Start time of the pinned past block (null = live); survives refreshes
+  const [pinnedStart, setPinnedStart] = useState<string | null>(null);
+  const [showHelp, setShowHelp] = useState(false);
+  const [modelFilter, setModelFilter] = useState("");
+  const [copied, setCopied] = useState<string | null>(null);
+  const [configReloaded, setConfigReloaded] = useState(false);
+  const modelFilterRef = useRef<HTMLInputElement>(null);
+  const modelDetailsRef = useRef<HTMLDetailsElement>(null);
+  // null = follow window size, true/false = forced with "m"
+  const [compactOverride, setCompactOverride] = useState<boolean | null>(null);
+  const [isSmallWindow, setIsSmallWindow] = useState(
+    () => window.innerWidth < COMPACT_WIDTH || window.innerHeight < COMPACT_HEIGHT
+  );
+  const [heatmap, setHeatmap] = useState<number[][] | null>(null);
+  const [dailyCosts, setDailyCosts] = useState<[string, number][]>([]);
   const intervalRef = useRef<number | null>(null);
+  // Formatters follow the display options of the last loaded data
+  const format = useMemo(() => makeFormat(data ?? DEFAULT_DISPLAY), [data]);
+  const { formatTokens, ic, formatCost, formatCostPrecise, formatTime, formatDayTime, formatDateTime, formatDay, formatDate } = format;
 
   useEffect(() => {
     applyTheme(currentTheme);
@@ -416,6 +836,19 @@ function App() {
     invoke<PlanLimits[]>("get_available_plans")
       .then(setPlans)
       .catch((e) => console.error("Failed to get plans:", e));
+    // --plan / the plan saved in the config; without either, guess from usage
+    invoke<number | null>("get_initial_plan")
+      .then((index) => (index !== null ? index : invoke<number | null>("get_inferred_plan")))
+      .then((index) => {
+        if (index !== null) setPlanIndex(index);
+      })
+      .catch((e) => console.error("Failed to get initial plan:", e));
+  }, []);
+
+  // Remember the last selected plan for the next launch (written to the config file)
+  const selectPlan = useCallback((index: number) => {
+    setPlanIndex(index);
+    invoke("save_selected_plan", { planIndex: index }).catch((e) => console.error("Failed to save plan:", e));
   }, []);
 
   const fetchData = useCallback(async () => {
@@ -427,6 +860,12 @@ function App() {
       setCountdown(result.current_block.secs_until_reset);
       setError(null);
       setLastRefresh(new Date());
+      invoke<number[][]>("get_usage_heatmap")
+        .then(setHeatmap)
+        .catch((e) => console.error("Failed to get heatmap:", e));
+      invoke<[string, number][]>("get_daily_costs", { days: DAILY_DAYS })
+        .then(setDailyCosts)
+        .catch((e) => console.error("Failed to get daily costs:", e));
     } catch (e) {
       setError(String(e));
     } finally {
@@ -434,10 +873,10 @@ function App() {
     }
   }, [planIndex]);
 
-  // Initial fetch
+  // Initial fetch, and again right away when the plan changes
   useEffect(() => {
     fetchData();
-  }, []);
+  }, [fetchData]);
 
   // Auto-refresh with configurable interval
   useEffect(() => {
@@ -448,7 +887,8 @@ function App() {
     }
 
     // Set new interval if auto-refresh is enabled
-    if (settings.autoRefresh && settings.refreshInterval > 0) {
+    // Piped (--stdin) data never changes, so there is nothing to poll
+    if (settings.autoRefresh && settings.refreshInterval > 0 && !data?.snapshot) {
       intervalRef.current = window.setInterval(fetchData, settings.refreshInterval * 1000);
     }
 
@@ -457,7 +897,163 @@ function App() {
         clearInterval(intervalRef.current);
       }
     };
-  }, [settings.autoRefresh, settings.refreshInterval, fetchData]);
+  }, [settings.autoRefresh, settings.refreshInterval, fetchData, data?.snapshot]);
+
+  // Refresh as soon as a log changes; stops when the backend has no watcher
+  useEffect(() => {
+    if (!settings.autoRefresh || data?.snapshot) return;
+    const timer = window.setInterval(async () => {
+      try {
+        const changed = await invoke<boolean | null>("take_logs_changed");
+        if (changed === null) clearInterval(timer);
+        else if (changed) fetchData();
+      } catch {
+        clearInterval(timer);
+      }
+    }, WATCH_POLL_MS);
+    return () => clearInterval(timer);
+  }, [settings.autoRefresh, fetchData, data?.snapshot]);
+
+  const toggleCostMode = useCallback(() => {
+    setSettings((prev) => {
+      const costMode: CostMode = prev.costMode === "limit" ? "real" : "limit";
+      const next = { ...prev, costMode };
+      storeSettings(next);
+      return next;
+    });
+  }, []);
+
+  const toggleRedact = useCallback(() => {
+    setSettings((prev) => {
+      const next = { ...prev, redactModels: !prev.redactModels };
+      storeSettings(next);
+      return next;
+    });
+  }, []);
+
+  // Copy the reset time (local) and time left to the clipboard
+  const copyResetTime = useCallback(() => {
+    const block = data?.current_block;
+    const text =
+      block?.is_active && block.reset_time
+        ? `Reset at ${formatTime(block.reset_time)} (in ${formatDuration(countdown)})`
+        : "no active block";
+    navigator.clipboard
+      .writeText(text)
+      .then(() => setCopied(text))
+      .catch((e) => console.error("Clipboard write failed:", e));
+  }, [data, countdown, formatTime]);
+
+  // Copy every block (times, reset, limit usage, models) as CSV
+  const copyBlocksCsv = useCallback(() => {
+    invoke<string>("get_blocks_csv")
+      .then((csv) => navigator.clipboard.writeText(csv))
+      .then(() => setCopied("block history CSV"))
+      .catch((e) => console.error("Block CSV export failed:", e));
+  }, []);
+
+  // Move the pin through past blocks (+1 = older); stepping newer than the newest goes back to live
+  const stepPinnedBlock = useCallback(
+    (delta: number) => {
+      const pastBlocks = (data?.block_history ?? []).filter((b) => !b.is_active);
+      setPinnedStart((start) => {
+        const current = pinnedBlockIndex(pastBlocks, start) ?? -1;
+        const next = Math.min(Math.max(current + delta, -1), pastBlocks.length - 1);
+        return next >= 0 ? pastBlocks[next].start_time : null;
+      });
+    },
+    [data]
+  );
+
+  // Hide the copy confirmation after a moment
+  useEffect(() => {
+    if (!copied) return;
+    const timer = setTimeout(() => setCopied(null), 2000);
+    return () => clearTimeout(timer);
+  }, [copied]);
+
+  // Edited config picked up: plans may have changed, show a brief note (once per reload)
+  const configReloadedAt = data?.config_reloaded_at;
+  const handledReloadRef = useRef<string | null>(null);
+  useEffect(() => {
+    if (!configReloadedAt || configReloadedAt === handledReloadRef.current) return;
+    handledReloadRef.current = configReloadedAt;
+    invoke<PlanLimits[]>("get_available_plans")
+      .then((next) => {
+        setPlans(next);
+        if (planIndex >= next.length) selectPlan(Math.max(next.length - 1, 0));
+      })
+      .catch((e) => console.error("Failed to get plans:", e));
+    setConfigReloaded(true);
+  }, [configReloadedAt, planIndex, selectPlan]);
+
+  // Hide the reload note after a moment
+  useEffect(() => {
+    if (!configReloaded) return;
+    const timer = setTimeout(() => setConfigReloaded(false), 3000);
+    return () => clearTimeout(timer);
+  }, [configReloaded]);
+
+  // Scale the custom plan's limits one step (saved to the config) and switch to it
+  const adjustCustomPlan = useCallback(
+    (direction: number) => {
+      invoke<number>("adjust_custom_plan", { planIndex, direction })
+        .then(async (index) => {
+          setPlans(await invoke<PlanLimits[]>("get_available_plans"));
+          if (index === planIndex) fetchData();
+          else selectPlan(index);
+        })
+        .catch((e) => console.error("Failed to adjust the custom plan:", e));
+    },
+    [planIndex, fetchData, selectPlan]
+  );
+
+  // Keyboard shortcuts
+  useEffect(() => {
+    const onKeyDown = (e: KeyboardEvent) => {
+      if (e.target instanceof HTMLInputElement || e.target instanceof HTMLSelectElement) return;
+      // While help is open only its own keys act
+      if (showHelp) {
+        if (e.key === "?" || e.key === "Escape") setShowHelp(false);
+        return;
+      }
+      if (e.key === "?") setShowHelp(true);
+      if (e.key === "h") setShowSparkline((v) => !v);
+      if (e.key === "v") setShowCompare((v) => !v);
+      if (e.key === "g") setShowVersions((v) => !v);
+      if (e.key === "[" || e.key === "]") stepPinnedBlock(e.key === "[" ? 1 : -1);
+      if (e.key === "l") setPinnedStart(null);
+      if (e.key === "c") toggleCostMode();
+      if (e.key === "r") fetchData();
+      if (e.key === "y") copyResetTime();
+      if (e.key === "e") copyBlocksCsv();
+      if (e.key === "+" || e.key === "=" || e.key === "-") adjustCustomPlan(e.key === "-" ? -1 : 1);
+      if (e.key === "a") toggleRedact();
+      if (e.key === "m") setCompactOverride((v) => !(v ?? isSmallWindow));
+      if (e.key === "/") {
+        // Open the model table and type into its filter
+        e.preventDefault();
+        if (modelDetailsRef.current) modelDetailsRef.current.open = true;
+        modelFilterRef.current?.focus();
+      }
+    };
+    window.addEventListener("keydown", onKeyDown);
+    return () => window.removeEventListener("keydown", onKeyDown);
+  }, [toggleCostMode, toggleRedact, isSmallWindow, fetchData, copyResetTime, copyBlocksCsv, adjustCustomPlan, showHelp, stepPinnedBlock]);
+
+  // Tick for the live/stale indicator
+  useEffect(() => {
+    const timer = setInterval(() => setClock(Date.now()), 1000);
+    return () => clearInterval(timer);
+  }, []);
+
+  // Compact layout follows the window size unless forced
+  useEffect(() => {
+    const onResize = () =>
+      setIsSmallWindow(window.innerWidth < COMPACT_WIDTH || window.innerHeight < COMPACT_HEIGHT);
+    window.addEventListener("resize", onResize);
+    return () => window.removeEventListener("resize", onResize);
+  }, []);
 
   useEffect(() => {
     if (countdown <= 0) return;
@@ -467,7 +1063,8 @@ function App() {
     return () => clearInterval(timer);
   }, [countdown > 0]);
 
-  if (error) {
+  // Full-page error only before the first successful load; afterwards keep stale data + banner
+  if (error && !data) {
     return (
       <div className="min-h-screen flex items-center justify-center p-8">
         <div className="card max-w-md text-center">
@@ -496,226 +1093,566 @@ function App() {
     );
   }
 
-  const { current_block, today, week, month, selected_plan, model_distribution, warnings } = data;
-
-  return (
-    <div className="min-h-screen p-4 space-y-4">
-      {/* Loading Overlay */}
-      <LoadingOverlay isVisible={isLoading} animations={settings.animations} />
-
-      {/* Settings Panel */}
-      <SettingsPanel
-        settings={settings}
-        onSettingsChange={setSettings}
-        isOpen={settingsOpen}
-        onClose={() => setSettingsOpen(false)}
-        plans={plans}
-      />
-
-      {/* Header */}
-      <header className="flex items-center justify-between">
-        <div>
-          <h1 className="text-xl font-bold text-gradient">Claude Dashboard</h1>
-          <div className="flex items-center gap-2 text-xs text-secondary">
-            <span>5h Rate Limit Tracker</span>
-            {lastRefresh && (
-              <span className="opacity-50">
-                • Last: {lastRefresh.toLocaleTimeString("fr-FR", { hour: "2-digit", minute: "2-digit", second: "2-digit" })}
-              </span>
-            )}
-            {settings.autoRefresh && (
-              <span className="text-success opacity-70">
-                • Auto {REFRESH_OPTIONS.find(o => o.value === settings.refreshInterval)?.label}
-              </span>
-            )}
-          </div>
-        </div>
-        <div className="flex items-center gap-2">
-          <select
-            value={planIndex}
-            onChange={(e) => setPlanIndex(Number(e.target.value))}
-            className="bg-secondary border border-white/10 rounded-lg px-2 py-1.5 text-sm focus:outline-none focus:border-accent-1 text-primary"
-          >
-            {plans.map((plan, i) => (
-              <option key={plan.name} value={i}>{plan.name}</option>
-            ))}
-          </select>
-          <ThemeSelector
-            currentTheme={currentTheme}
-            onThemeChange={handleThemeChange}
-            isOpen={themeMenuOpen}
-            onToggle={() => setThemeMenuOpen(!themeMenuOpen)}
-          />
-          <button
-            onClick={() => setSettingsOpen(true)}
-            className="theme-btn p-2"
-            title="Settings"
-          >
-            ⚙️
-          </button>
+  // No usage logged yet: explain where logs are expected instead of showing zeros
+  if (data.empty_state) {
+    const { data_dir, missing } = data.empty_state;
+    return (
+      <div className="min-h-screen flex items-center justify-center p-8">
+        <div className="card max-w-md text-center">
+          <div className="text-4xl mb-4">{ic("👋", "")}</div>
+          <h2 className="text-xl font-bold mb-2">No Claude usage yet</h2>
+          <p className="text-secondary">
+            {missing ? "The log directory doesn't exist yet:" : "No usage logs found in:"}
+          </p>
+          <p className="font-mono text-xs text-accent-1 my-2 break-all">{data_dir}</p>
+          <p className="text-secondary text-sm">
+            Usage will appear here once Claude Code runs on this machine (or set CLAUDE_DATA_DIR to your logs).
+          </p>
           <button
             onClick={fetchData}
-            disabled={isLoading}
-            className={`theme-btn p-2 ${isLoading ? 'opacity-50 cursor-not-allowed' : ''}`}
-            title="Refresh"
+            className="mt-4 px-4 py-2 bg-accent-1 hover:opacity-80 rounded-lg transition-all"
           >
-            <span className={isLoading && settings.animations ? 'animate-spin inline-block' : ''}>🔄</span>
+            Refresh
           </button>
         </div>
-      </header>
-
-      {/* Warnings */}
-      <WarningBanner warnings={warnings} />
-
-      {/* ═══════════════════════════════════════════════════════════════════
-          ZONE PRINCIPALE — Métriques claude-dashboard (référence précise)
-          ═══════════════════════════════════════════════════════════════════ */}
-      <section className="card glow">
-        {/* Header with status + countdown */}
-        <div className="flex items-center justify-between mb-6">
-          <div className="flex items-center gap-3">
-            <div
-              className={`w-3 h-3 rounded-full ${
-                current_block.is_active ? "bg-success animate-pulse" : "bg-secondary opacity-50"
-              }`}
+      </div>
+    );
+  }
+
+  const compact = compactOverride ?? isSmallWindow;
+  // Live while auto-refresh keeps succeeding; stale once refreshes fail or stop arriving
+  const refreshAge = lastRefresh ? (clock - lastRefresh.getTime()) / 1000 : Infinity;
+  const isStale =
+    !!error ||
+    (settings.autoRefresh && refreshAge > settings.refreshInterval * STALE_INTERVALS + STALE_SLACK_SECS);
+  const pastBlocks = data.block_history.filter((b) => !b.is_active);
+  const pinnedIndex = pinnedBlockIndex(pastBlocks, pinnedStart);
+  const pinnedBlock = pinnedIndex !== null ? pastBlocks[pinnedIndex] : null;
+  const modelQuery = modelFilter.trim().toLowerCase();
+  const modelName = (m: { model: string; tier: string }) =>
+    settings.redactModels ? aliasModel(m.model, m.tier) : m.model;
+  const filteredModels = data.today.models.filter((m) => modelName(m).toLowerCase().includes(modelQuery));
+  const modelTotals = filteredModels.reduce(
+    (t, m) => ({
+      calls: t.calls + m.call_count,
+      input: t.input + m.input_tokens,
+      output: t.output + m.output_tokens,
+      cache: t.cache + m.cache_create_tokens + m.cache_read_tokens,
+      cost: t.cost + m.cost,
+    }),
+    { calls: 0, input: 0, output: 0, cache: 0, cost: 0 }
+  );
+  const costMode = settings.costMode;
+  const isLimitMode = costMode === "limit";
+  const { current_block, today, week, month, all_time, yesterday, last_week, selected_plan, model_distribution, block_history, block_costs, maxed_blocks, weekly, projects, tier_breakdown, tier_fallbacks, sessions, trends, costliest_call_today, costliest_call_month, warnings } = data;
+
+  return (
+    <FormatContext.Provider value={format}>
+      <div className="min-h-screen p-4 space-y-4">
+        {/* Loading Overlay */}
+        <LoadingOverlay isVisible={isLoading} animations={settings.animations} />
+
+        {/* Settings Panel */}
+        <SettingsPanel
+          settings={settings}
+          onSettingsChange={setSettings}
+          isOpen={settingsOpen}
+          onClose={() => setSettingsOpen(false)}
+          plans={plans}
+          planIndex={planIndex}
+          onSelectPlan={selectPlan}
+        />
+
+        {/* Keyboard Help */}
+        <HelpOverlay isOpen={showHelp} onClose={() => setShowHelp(false)} />
+
+        {/* Header */}
+        <header className="flex items-center justify-between">
+          <div>
+            <h1 className="text-xl font-bold text-gradient">Claude Dashboard</h1>
+            <div className="flex items-center gap-2 text-xs text-secondary">
+              <span>5h Rate Limit Tracker</span>
+              {!data.snapshot && (
+                <span
+                  className={isStale ? "text-secondary" : "text-success"}
+                  title={isStale ? "Data is not refreshing (see the error banner or refresh manually)" : "Data is current"}
+                >
+                  <span className={!isStale && settings.animations ? "animate-pulse" : ""}>●</span>{" "}
+                  {isStale ? "stale" : "live"}
+                </span>
+              )}
+              {lastRefresh && (
+                <span className="opacity-50">
+                  • Last: {lastRefresh.toLocaleTimeString("fr-FR", { hour: "2-digit", minute: "2-digit", second: "2-digit" })}
+                </span>
+              )}
+              {data.snapshot ? (
+                <span className="text-warning opacity-70">• Snapshot (stdin)</span>
+              ) : settings.autoRefresh && (
+                <span className="text-success opacity-70">
+                  • Auto {REFRESH_OPTIONS.find(o => o.value === settings.refreshInterval)?.label}
+                </span>
+              )}
+            </div>
+          </div>
+          <div className="flex items-center gap-2">
+            <select
+              value={planIndex}
+              onChange={(e) => selectPlan(Number(e.target.value))}
+              className="bg-secondary border border-white/10 rounded-lg px-2 py-1.5 text-sm focus:outline-none focus:border-accent-1 text-primary"
+            >
+              {plans.map((plan, i) => (
+                <option key={plan.name} value={i}>{plan.name}</option>
+              ))}
+            </select>
+            <button
+              onClick={toggleCostMode}
+              className="theme-btn px-2 py-1.5 text-xs font-mono uppercase"
+              title="Toggle limit / real cost (c)"
+            >
+              {costMode}
+            </button>
+            <ThemeSelector
+              currentTheme={currentTheme}
+              onThemeChange={handleThemeChange}
+              isOpen={themeMenuOpen}
+              onToggle={() => setThemeMenuOpen(!themeMenuOpen)}
             />
+            <button
+              onClick={() => setSettingsOpen(true)}
+              className="theme-btn p-2"
+              title="Settings"
+            >
+              ⚙️
+            </button>
+            <button
+              onClick={fetchData}
+              disabled={isLoading}
+              className={`theme-btn p-2 ${isLoading ? 'opacity-50 cursor-not-allowed' : ''}`}
+              title="Refresh"
+            >
+              <span className={isLoading && settings.animations ? 'animate-spin inline-block' : ''}>🔄</span>
+            </button>
+          </div>
+        </header>
+
+        {/* Refresh error (data below is stale) */}
+        {error && (
+          <div className="bg-error/20 border border-error/50 rounded-lg p-3 flex items-center justify-between gap-3">
+            <div className="text-error text-sm">
+              <span className="font-medium">Refresh failed:</span> {error}
+              {lastRefresh && (
+                <span className="opacity-70">
+                  {" "}• showing data from {lastRefresh.toLocaleTimeString("fr-FR", { hour: "2-digit", minute: "2-digit" })}
+                </span>
+              )}
+            </div>
+            <button onClick={fetchData} className="theme-btn px-2 py-1 text-xs">Retry</button>
+          </div>
+        )}
+
+        {/* Warnings */}
+        <WarningBanner warnings={warnings} />
+
+        {/* ═══════════════════════════════════════════════════════════════════
+            ZONE PRINCIPALE — Métriques claude-dashboard (référence précise)
+            ═══════════════════════════════════════════════════════════════════ */}
+        {pinnedBlock ? (
+          <PinnedBlockCard block={pinnedBlock} plan={selected_plan} onLive={() => setPinnedStart(null)} />
+        ) : (
+          <section className="card glow">
+            {/* Header with status + countdown */}
+            <div className="flex items-center justify-between mb-6">
+              <div className="flex items-center gap-3">
+                <div
+                  className={`w-3 h-3 rounded-full ${
+                    current_block.is_active ? "bg-success animate-pulse" : "bg-secondary opacity-50"
+                  }`}
+                />
+                <div>
+                  <h2 className="text-lg font-bold flex items-center gap-2">
+                    Current Block
+                    {current_block.live_call && (
+                      <span className={`badge badge-haiku text-xs ${settings.animations ? "animate-pulse" : ""}`}>
+                        {ic("⚡", "*")} Live call +{formatCost(current_block.live_call_cost)}
+                      </span>
+                    )}
+                  </h2>
+                  <span className="text-xs text-secondary">
+                    {current_block.block_start
+                      ? `${formatTime(current_block.block_start)} → ${formatTime(current_block.reset_time)}`
+                      : current_block.last_block_end
+                        ? `Reset complete, full limit available • last block ended ${formatDuration(
+                            Math.floor((Date.now() - new Date(current_block.last_block_end).getTime()) / 1000)
+                          )} ago`
+                        : "No active block"}
+                  </span>
+                </div>
+              </div>
+              <div className="text-right">
+                <div className="text-3xl font-mono font-bold text-accent-1">
+                  {formatDuration(countdown)}
+                </div>
+                <div className="text-xs text-secondary">until reset</div>
+              </div>
+            </div>
+
+            {/* Main Stats - Like claude-dashboard */}
+            <div className="grid grid-cols-3 gap-6 mb-6">
+              <MainStat
+                icon={ic("💰", "$")}
+                label={`Cost (${costMode})`}
+                value={formatCost(isLimitMode ? current_block.limit_cost : current_block.real_cost)}
+                max={formatCost(selected_plan.cost_limit)}
+              />
+              <MainStat
+                icon={ic("🎯", "#")}
+                label={`Tokens (${costMode})`}
+                value={formatTokens(isLimitMode ? current_block.limit_tokens : current_block.real_tokens)}
+                max={formatTokens(selected_plan.token_limit)}
+              />
+              <MainStat
+                icon={ic("💬", ">")}
+                label="Messages"
+                value={current_block.limit_messages.toString()}
+                max={selected_plan.message_limit > 0 ? selected_plan.message_limit.toString() : "∞"}
+              />
+            </div>
+
+            {/* Progress Bars */}
+            <div className="space-y-3">
+              <ProgressBar
+                value={isLimitMode ? current_block.limit_cost : current_block.real_cost}
+                max={selected_plan.cost_limit}
+                softMax={data.thresholds.soft_cost_limit}
+                accentClass="accent-1"
+                label="Cost"
+              />
+              <ProgressBar
+                value={isLimitMode ? current_block.limit_tokens : current_block.real_tokens}
+                max={selected_plan.token_limit}
+                softMax={data.thresholds.soft_token_limit}
+                accentClass="accent-2"
+                label="Tokens"
+              />
+              {/* message_limit 0 = unlimited, no percentage to show */}
+              {selected_plan.message_limit > 0 && (
+                <ProgressBar
+                  value={current_block.limit_messages}
+                  max={selected_plan.message_limit}
+                  accentClass="accent-3"
+                  label="Messages"
+                />
+              )}
+              {/* Per-tier caps (e.g. Opus) from the plan, always in limit cost */}
+              {current_block.tier_limits.map((t) => (
+                <ProgressBar
+                  key={t.tier}
+                  value={t.limit_cost}
+                  max={t.cost_limit}
+                  accentClass="accent-3"
+                  label={`${t.tier} cost`}
+                />
+              ))}
+            </div>
+          </section>
+        )}
+
+        {/* Compact layout (small window or "m"): one-line summary, details hidden */}
+        {compact && (
+          <div className="text-xs text-secondary text-center font-mono">
+            Today {formatCost(isLimitMode ? today.limit_cost : today.total_cost)} • Week{" "}
+            {formatCost(isLimitMode ? week.limit_cost : week.total_cost)} • Month{" "}
+            {formatCost(isLimitMode ? month.limit_cost : month.total_cost)}
+          </div>
+        )}
+
+        {!compact && (
+        <>
+        {/* Tokens per Hour of the current block */}
+        {current_block.is_active && current_block.hourly_tokens.length > 0 && (
+          <section className="card">
+            <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider">
+              {ic("⏱️", "")} Tokens per Hour — current block
+            </h3>
+            <HourlyBars values={current_block.hourly_tokens} />
+          </section>
+        )}
+
+        {/* Cost per Block Sparkline (toggle: h) */}
+        {showSparkline && (
+          <section className="card">
+            <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider flex justify-between">
+              <span>{ic("📉", "~")} Cost per Block — last {block_costs.length}</span>
+              <span className="opacity-50 normal-case">h to hide</span>
+            </h3>
+            <Sparkline values={block_costs} />
+          </section>
+        )}
+
+        {/* Weekly Window */}
+        <section className="card">
+          <div className="flex items-center justify-between mb-3">
             <div>
-              <h2 className="text-lg font-bold">Current Block</h2>
+              <h3 className="text-xs font-semibold text-secondary uppercase tracking-wider">{ic("📅", "")} Weekly Window</h3>
               <span className="text-xs text-secondary">
-                {current_block.block_start ? `${formatTime(current_block.block_start)} → ${formatTime(current_block.reset_time)}` : "No active block"}
+                {formatDayTime(weekly.week_start)} → {formatDayTime(weekly.reset_time)}
               </span>
             </div>
-          </div>
-          <div className="text-right">
-            <div className="text-3xl font-mono font-bold text-accent-1">
-              {formatDuration(countdown)}
+            <div className="text-right">
+              <div className="text-xl font-mono font-bold text-accent-1">{formatDuration(weekly.secs_until_reset)}</div>
+              <div className="text-xs text-secondary">until weekly reset</div>
             </div>
-            <div className="text-xs text-secondary">until reset</div>
           </div>
+          {weekly.cost_limit > 0 ? (
+            <ProgressBar value={weekly.limit_cost} max={weekly.cost_limit} accentClass="accent-1" label="Window cost" />
+          ) : (
+            <div className="grid grid-cols-3 gap-x-4">
+              <MiniStat label="Cost" value={formatCost(weekly.limit_cost)} color="text-accent-1" />
+              <MiniStat label="Tokens" value={formatTokens(weekly.limit_tokens)} color="text-accent-2" />
+              <MiniStat label="Messages" value={weekly.limit_messages.toString()} color="text-success" />
+            </div>
+          )}
+        </section>
+
+        {/* Plan weekly caps (rolling 7 days), only when the plan sets them */}
+        {data.weekly_limits.enabled && (
+          <section className="card">
+            <div className="flex items-center justify-between mb-3">
+              <h3 className="text-xs font-semibold text-secondary uppercase tracking-wider">
+                {ic("🗓️", "")} Weekly Caps — last 7 days
+              </h3>
+              {data.weekly_limits.reset_estimate && (
+                <span className="text-xs text-secondary">frees up from {formatDayTime(data.weekly_limits.reset_estimate)}</span>
+              )}
+            </div>
+            <div className="space-y-3">
+              {data.weekly_limits.cost_limit > 0 && (
+                <ProgressBar
+                  value={data.weekly_limits.limit_cost}
+                  max={data.weekly_limits.cost_limit}
+                  accentClass="accent-1"
+                  label="7-day cost"
+                />
+              )}
+              {data.weekly_limits.token_limit > 0 && (
+                <ProgressBar
+                  value={data.weekly_limits.limit_tokens}
+                  max={data.weekly_limits.token_limit}
+                  accentClass="accent-2"
+                  label="7-day tokens"
+                />
+              )}
+            </div>
+          </section>
+        )}
+
+        {/* ═══════════════════════════════════════════════════════════════════
+            ZONE SECONDAIRE — Infos supplémentaires (bonus)
+            ═══════════════════════════════════════════════════════════════════ */}
+        <div className="grid grid-cols-4 gap-3">
+          {/* Burn Rate */}
+          <InfoCard title="Burn Rate" icon={ic("🔥", "~")}>
+            <MiniStat label="Tokens/min" value={current_block.tokens_per_min.toFixed(0)} color="text-accent-2" />
+            <MiniStat label="Cost/min" value={formatCost(current_block.cost_per_min)} color="text-accent-1" />
+            <MiniStat label="Active" value={`${current_block.active_minutes.toFixed(0)}m`} color="text-success" />
+          </InfoCard>
+
+          {/* Predictions */}
+          <InfoCard title="Predictions" icon={ic("🔮", "?")}>
+            <MiniStat
+              label="Tokens out"
+              value={current_block.tokens_exhausted_at ? formatTime(current_block.tokens_exhausted_at) : ic("Safe ✓", "Safe")}
+              color={current_block.tokens_exhausted_at ? "text-warning" : "text-success"}
+            />
+            <MiniStat
+              label="Cost out"
+              value={current_block.cost_exhausted_at ? formatTime(current_block.cost_exhausted_at) : ic("Safe ✓", "Safe")}
+              color={current_block.cost_exhausted_at ? "text-warning" : "text-success"}
+            />
+            {current_block.is_active && (
+              <div
+                className={`text-xs mt-1 ${current_block.verdict.kind === "WillExhaust" ? "text-error" : "text-success"}`}
+              >
+                {current_block.verdict.kind === "WillExhaust"
+                  ? `Cap hit ~${formatDuration(current_block.verdict.before_secs)} before reset`
+                  : "Makes it to reset"}
+              </div>
+            )}
+          </InfoCard>
+
+          {/* Real Usage (with cache) */}
+          <InfoCard title="Real Usage" icon={ic("📈", "$")}>
+            <MiniStat label="Real cost" value={formatCost(current_block.real_cost)} color="text-accent-1" />
+            <MiniStat label="Real tokens" value={formatTokens(current_block.real_tokens)} color="text-accent-2" />
+            <MiniStat
+              label="Cache saved"
+              value={formatCost(Math.max(0, current_block.real_cost - current_block.limit_cost))}
+              color="text-success"
+            />
+          </InfoCard>
+
+          {/* Model Distribution */}
+          <InfoCard title={showVersions ? "Model Versions" : "Models"} icon={ic("🤖", "#")}>
+            {model_distribution.length > 0 ? (
+              showVersions ? (
+                data.model_version_distribution.map((dist) => (
+                  <ModelDistBar key={dist.model} dist={dist} label={modelName(dist)} />
+                ))
+              ) : (
+                model_distribution.map((dist) => <ModelDistBar key={dist.tier} dist={dist} />)
+              )
+            ) : (
+              <div className="text-xs text-secondary">No data</div>
+            )}
+          </InfoCard>
         </div>
 
-        {/* Main Stats - Like claude-dashboard */}
-        <div className="grid grid-cols-3 gap-6 mb-6">
-          <MainStat
-            icon="💰"
-            label="Cost"
-            value={formatCost(current_block.limit_cost)}
-            max={formatCost(selected_plan.cost_limit)}
-          />
-          <MainStat
-            icon="🎯"
-            label="Tokens"
-            value={formatTokens(current_block.limit_tokens)}
-            max={formatTokens(selected_plan.token_limit)}
-          />
-          <MainStat
-            icon="💬"
-            label="Messages"
-            value={current_block.limit_messages.toString()}
-            max={selected_plan.message_limit.toString()}
-          />
+        {/* Period comparison (toggle: v) */}
+        {showCompare && (
+          <div className="grid grid-cols-2 gap-3">
+            <CompareTable cur={today} prev={yesterday} limitMode={isLimitMode} />
+            <CompareTable cur={week} prev={last_week} limitMode={isLimitMode} />
+          </div>
+        )}
+
+        {/* Period Stats */}
+        <div className="grid grid-cols-3 gap-3">
+          {[today, week, month].map((period) => (
+            <div key={period.period_label} className="card">
+              <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider">
+                {period.period_label}
+              </h3>
+              <div className="grid grid-cols-2 gap-x-4">
+                <MiniStat
+                  label="Cost"
+                  value={formatCostPrecise(isLimitMode ? period.limit_cost : period.total_cost)}
+                  color="text-accent-1"
+                />
+                <MiniStat
+                  label="Tokens"
+                  value={formatTokens(isLimitMode ? period.limit_tokens : period.total_tokens)}
+                  color="text-accent-2"
+                />
+                <MiniStat label="Calls" value={period.total_calls.toString()} color="text-success" />
+                <MiniStat label="Sessions" value={period.session_count.toString()} />
+              </div>
+              <div className="text-xs text-secondary mt-1">
+                <span title="Total cost per 1M output tokens">{formatCost(period.cost_per_output_mtok)} / M out</span>
+                {" • "}
+                <span title="Average cost and tokens per session">
+                  {formatCost(period.avg_cost_per_session)} / {formatTokens(Math.round(period.avg_tokens_per_session))} per session
+                </span>
+              </div>
+            </div>
+          ))}
         </div>
 
-        {/* Progress Bars */}
-        <div className="space-y-3">
-          <ProgressBar
-            value={current_block.limit_cost}
-            max={selected_plan.cost_limit}
-            accentClass="accent-1"
-            label="Cost"
-          />
-          <ProgressBar
-            value={current_block.limit_tokens}
-            max={selected_plan.token_limit}
-            accentClass="accent-2"
-            label="Tokens"
-          />
-          <ProgressBar
-            value={current_block.limit_messages}
-            max={selected_plan.message_limit}
-            accentClass="accent-3"
-            label="Messages"
-          />
+        {/* Trends: today vs week vs month */}
+        <div className="card">
+          <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider">
+            Trends{" "}
+            <span className={trends.accelerating ? "text-error" : "text-success"}>
+              {trends.accelerating ? ic("▲ above", "^ above") : ic("▼ below", "v below")} daily average
+            </span>
+          </h3>
+          <div className="grid grid-cols-3 gap-x-4">
+            <MiniStat label="Today / Week" value={`${trends.today_of_week_percent.toFixed(0)}%`} color="text-accent-1" />
+            <MiniStat label="Week / Month" value={`${trends.week_of_month_percent.toFixed(0)}%`} color="text-accent-2" />
+            <MiniStat
+              label="Avg / day"
+              value={`${formatCost(trends.week_daily_avg)} wk • ${formatCost(trends.month_daily_avg)} mo`}
+            />
+          </div>
+          <div className="text-xs text-secondary mt-1 font-mono">
+            proj ~{formatCost(trends.projected_end_of_day)} by EOD • ~{formatCost(trends.projected_end_of_month)} by end of month
+          </div>
+          <div className="text-xs text-secondary mt-1 font-mono" title="Limit cost counts the categories in the limit policy; the bill prices every token">
+            month: {formatCost(month.limit_cost)} counts toward limit •
+            est. bill {formatCost(month.total_cost)} (~{formatCost(trends.projected_month_bill)} by end of month)
+          </div>
         </div>
-      </section>
-
-      {/* ═══════════════════════════════════════════════════════════════════
-          ZONE SECONDAIRE — Infos supplémentaires (bonus)
-          ═══════════════════════════════════════════════════════════════════ */}
-      <div className="grid grid-cols-4 gap-3">
-        {/* Burn Rate */}
-        <InfoCard title="Burn Rate" icon="🔥">
-          <MiniStat label="Tokens/min" value={current_block.tokens_per_min.toFixed(0)} color="text-accent-2" />
-          <MiniStat label="Cost/min" value={formatCost(current_block.cost_per_min)} color="text-accent-1" />
-          <MiniStat label="Active" value={`${current_block.active_minutes.toFixed(0)}m`} color="text-success" />
-        </InfoCard>
-
-        {/* Predictions */}
-        <InfoCard title="Predictions" icon="🔮">
-          <MiniStat
-            label="Tokens out"
-            value={current_block.tokens_exhausted_at ? formatTime(current_block.tokens_exhausted_at) : "Safe ✓"}
-            color={current_block.tokens_exhausted_at ? "text-warning" : "text-success"}
-          />
-          <MiniStat
-            label="Cost out"
-            value={current_block.cost_exhausted_at ? formatTime(current_block.cost_exhausted_at) : "Safe ✓"}
-            color={current_block.cost_exhausted_at ? "text-warning" : "text-success"}
-          />
-        </InfoCard>
-
-        {/* Real Usage (with cache) */}
-        <InfoCard title="Real Usage" icon="📈">
-          <MiniStat label="Real cost" value={formatCost(current_block.real_cost)} color="text-accent-1" />
-          <MiniStat label="Real tokens" value={formatTokens(current_block.real_tokens)} color="text-accent-2" />
-          <MiniStat
-            label="Cache saved"
-            value={formatCost(Math.max(0, current_block.real_cost - current_block.limit_cost))}
-            color="text-success"
-          />
-        </InfoCard>
-
-        {/* Model Distribution */}
-        <InfoCard title="Models" icon="🤖">
-          {model_distribution.length > 0 ? (
-            model_distribution.map((dist) => (
-              <ModelDistBar key={dist.tier} dist={dist} />
-            ))
-          ) : (
-            <div className="text-xs text-secondary">No data</div>
-          )}
-        </InfoCard>
-      </div>
 
-      {/* Period Stats */}
-      <div className="grid grid-cols-3 gap-3">
-        {[today, week, month].map((period) => (
-          <div key={period.period_label} className="card">
+        {/* Outliers: single most expensive call */}
+        {costliest_call_month && (
+          <div className="card">
             <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider">
-              {period.period_label}
+              {ic("💥", "!")} Most Expensive Call
             </h3>
-            <div className="grid grid-cols-2 gap-x-4">
-              <MiniStat label="Cost" value={formatCost(period.total_cost)} color="text-accent-1" />
-              <MiniStat label="Tokens" value={formatTokens(period.total_tokens)} color="text-accent-2" />
-              <MiniStat label="Calls" value={period.total_calls.toString()} color="text-success" />
-              <MiniStat label="Sessions" value={period.session_count.toString()} />
-            </div>
+            {([["Today", costliest_call_today], ["This Month", costliest_call_month]] as const).map(([label, call]) =>
+              call ? (
+                <div key={label} className="flex items-center justify-between text-xs font-mono py-0.5">
+                  <span className="text-secondary">{label}</span>
+                  <span>
+                    <span className="text-accent-1">{formatCost(call[2])}</span>
+                    <span className="text-primary"> on {modelName({ model: call[1], tier: month.models.find((m) => m.model === call[1])?.tier ?? "" })}</span>
+                    <span className="text-secondary"> at {formatDateTime(call[0])}</span>
+                  </span>
+                </div>
+              ) : null
+            )}
           </div>
-        ))}
-      </div>
+        )}
+
+        {/* All Time */}
+        <div className="card">
+          <h3 className="text-xs font-semibold text-secondary mb-2 uppercase tracking-wider">
+            {all_time.period_label}
+            <span className="normal-case opacity-70">
+              {all_time.first_timestamp
+                ? ` — since ${formatDate(all_time.first_timestamp)}`
+                : " — no data"}
+            </span>
+          </h3>
+          <div className="grid grid-cols-4 gap-x-4">
+            <MiniStat
+              label="Cost"
+              value={formatCostPrecise(isLimitMode ? all_time.limit_cost : all_time.total_cost)}
+              color="text-accent-1"
+            />
+            <MiniStat
+              label="Tokens"
+              value={formatTokens(isLimitMode ? all_time.limit_tokens : all_time.total_tokens)}
+              color="text-accent-2"
+            />
+            <MiniStat label="Calls" value={all_time.total_calls.toString()} color="text-success" />
+            <MiniStat label="Sessions" value={all_time.session_count.toString()} />
+          </div>
+        </div>
 
-      {/* Model Breakdown Today - Collapsible detail */}
-      {today.models.length > 0 && (
-        <details className="card">
+        {/* Maxed-out blocks this month */}
+        <div className="card text-xs flex items-center gap-2">
+          <span>{ic("🚧", "[!]")}</span>
+          {maxed_blocks.length > 0 ? (
+            <span className="text-warning">
+              Maxed out {maxed_blocks.length} time(s) this month, last: {formatDay(maxed_blocks[maxed_blocks.length - 1])}
+            </span>
+          ) : (
+            <span className="text-success">Never maxed out this month</span>
+          )}
+        </div>
+
+        {/* Model Breakdown Today - Collapsible detail */}
+        <details className="card" ref={modelDetailsRef}>
           <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
-            📊 Model Details (Today) — {today.models.length} model(s)
+            {ic("📊", "#")} Model Details (Today) — {today.models.length} model(s)
           </summary>
+          <input
+            ref={modelFilterRef}
+            value={modelFilter}
+            onChange={(e) => setModelFilter(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === "Escape") {
+                setModelFilter("");
+                e.currentTarget.blur();
+              }
+            }}
+            placeholder="Filter models ( / )"
+            className="mt-3 w-full bg-secondary border border-white/10 rounded-lg px-2 py-1 text-xs focus:outline-none focus:border-accent-1 text-primary"
+          />
           <div className="mt-3 space-y-2">
-            {today.models.map((model) => {
-              const tier = getTierBadge(
-                model.model.toLowerCase().includes("opus") ? "Opus" :
-                model.model.toLowerCase().includes("haiku") ? "Haiku" : "Sonnet"
-              );
+            {filteredModels.map((model) => {
+              const tier = getTierBadge(model.tier);
               const totalTokens = model.input_tokens + model.output_tokens + model.cache_create_tokens + model.cache_read_tokens;
               return (
                 <div
@@ -724,24 +1661,220 @@ function App() {
                 >
                   <div className="flex items-center gap-2">
                     <span className={`badge ${tier.class}`}>{tier.name}</span>
-                    <span className="text-xs text-primary truncate max-w-[180px]">{model.model}</span>
+                    <span className="text-xs text-primary truncate max-w-[180px]">{modelName(model)}</span>
+                    {model.tier === "Unknown" && (
+                      <span className="text-xs text-warning" title="Unknown model: cost estimated at Sonnet rates">
+                        ~est.
+                      </span>
+                    )}
                   </div>
                   <div className="flex items-center gap-3 text-xs font-mono">
+                    <TokenSplitBar
+                      input={model.input_tokens}
+                      output={model.output_tokens}
+                      cacheRead={model.cache_read_tokens}
+                    />
                     <span className="text-accent-2">{formatTokens(totalTokens)}</span>
+                    <span
+                      className="text-success"
+                      title={`Cache hit ratio • saved ~${formatCost(model.cache_savings)} vs full input price`}
+                    >
+                      {model.cache_hit_percent.toFixed(0)}% hit
+                    </span>
+                    <span className="text-accent-1" title="Effective price: total cost per 1M output tokens">
+                      {formatCost(model.cost_per_output_mtok)}/M
+                    </span>
                     <span className="text-secondary">{model.call_count} calls</span>
                   </div>
                 </div>
               );
             })}
+            {/* Totals for the rows shown (follows the filter) */}
+            {filteredModels.length === 0 ? (
+              <div className="text-xs text-secondary">
+                {today.models.length === 0 ? "no usage in this period" : "no model matches the filter"}
+              </div>
+            ) : (
+              <div className="flex items-center justify-between mt-2 pt-2 border-t border-white/10 text-xs font-mono font-bold">
+                <span className="text-primary">
+                  TOTAL{modelQuery && ` (${filteredModels.length} of ${today.models.length})`}
+                </span>
+                <div className="flex items-center gap-3">
+                  <span className="text-secondary" title="input / output / cache">
+                    {formatTokens(modelTotals.input)} / {formatTokens(modelTotals.output)} / {formatTokens(modelTotals.cache)}
+                  </span>
+                  <span className="text-accent-1">{formatCost(modelTotals.cost)}</span>
+                  <span className="text-secondary">{modelTotals.calls} calls</span>
+                </div>
+              </div>
+            )}
           </div>
         </details>
-      )}
 
-      {/* Footer */}
-      <footer className="text-center text-xs text-secondary opacity-50">
-        Claude Dashboard v0.8.4 • {selected_plan.name} • {themes[currentTheme]?.name}
-      </footer>
-    </div>
+        {/* Block History - Collapsible detail */}
+        {block_history.length > 0 && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("🕐", "#")} Block History — last {block_history.length} block(s)
+            </summary>
+            <div className="mt-3 space-y-2 max-h-64 overflow-y-auto">
+              {block_history.map((block) => (
+                <div
+                  key={block.start_time}
+                  onClick={() => setPinnedStart(block.is_active ? null : block.start_time)}
+                  title={block.is_active ? "Live block" : "Pin this block"}
+                  className={`flex items-center justify-between py-1 border-b border-white/5 last:border-0 cursor-pointer hover:bg-white/5 ${
+                    block.start_time === pinnedBlock?.start_time ? "bg-white/10" : ""
+                  }`}
+                >
+                  <div className="flex items-center gap-2 text-xs">
+                    <div className={`w-2 h-2 rounded-full ${block.is_active ? "bg-success" : "bg-secondary opacity-50"}`} />
+                    <span className="text-primary">{formatDayTime(block.start_time)} → {formatTime(block.end_time)}</span>
+                  </div>
+                  <div className="flex items-center gap-3 text-xs font-mono">
+                    <span className="text-accent-1">{formatCost(block.limit_cost)}</span>
+                    <span className="text-accent-2">{formatTokens(block.limit_tokens)}</span>
+                    <span className="text-secondary">{block.messages} msgs</span>
+                  </div>
+                </div>
+              ))}
+            </div>
+          </details>
+        )}
+
+        {/* Project Breakdown This Month - Collapsible detail */}
+        {projects.length > 0 && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("📁", "#")} Projects (This Month) — {projects.length} project(s)
+            </summary>
+            <div className="mt-3 space-y-2">
+              {projects.map(([project, stats]) => (
+                <div
+                  key={project}
+                  className="flex items-center justify-between py-1 border-b border-white/5 last:border-0"
+                >
+                  <span className="text-xs text-primary truncate max-w-[240px]" title={project}>{project}</span>
+                  <div className="flex items-center gap-3 text-xs font-mono">
+                    <span className="text-accent-1">{formatCost(stats.total_cost)}</span>
+                    <span className="text-accent-2">{formatTokens(stats.total_tokens)}</span>
+                    <span className="text-secondary">{stats.total_calls} calls</span>
+                  </div>
+                </div>
+              ))}
+            </div>
+          </details>
+        )}
+
+        {/* Cost per tier this month - Collapsible detail */}
+        {tier_breakdown.length > 0 && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("🧮", "#")} Tiers (This Month) — {formatCost(month.total_cost)}
+            </summary>
+            <div className="mt-3 space-y-2">
+              {tier_breakdown.map((t) => {
+                const badge = getTierBadge(t.tier);
+                const percent = month.total_cost > 0 ? (t.cost / month.total_cost) * 100 : 0;
+                return (
+                  <div key={t.tier} className="flex items-center justify-between py-1 border-b border-white/5 last:border-0">
+                    <span className={`badge ${badge.class}`}>{badge.name}</span>
+                    <div className="flex items-center gap-3 text-xs font-mono">
+                      <span className="text-accent-1">{formatCost(t.cost)}</span>
+                      <span className="text-secondary">{percent.toFixed(0)}%</span>
+                      <span className="text-secondary">{t.calls} calls</span>
+                    </div>
+                  </div>
+                );
+              })}
+              <div
+                className="text-xs text-secondary font-mono"
+                title={tier_fallbacks.timestamps.map(formatDateTime).join("\n") || "No downgrade within a session"}
+              >
+                tier fallbacks: {tier_fallbacks.fallback_count}
+              </div>
+            </div>
+          </details>
+        )}
+
+        {/* Most expensive sessions this month - Collapsible detail */}
+        {sessions.length > 0 && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("💬", "#")} Top Sessions (This Month) — {sessions.length} session(s)
+            </summary>
+            <div className="mt-3 space-y-2">
+              {sessions.map(([session, stats]) => (
+                <div
+                  key={session}
+                  className="flex items-center justify-between py-1 border-b border-white/5 last:border-0"
+                >
+                  <span className="text-xs text-primary font-mono" title={session}>{session.slice(0, 8)}</span>
+                  <div className="flex items-center gap-3 text-xs font-mono">
+                    <span className="text-accent-1">{formatCost(stats.total_cost)}</span>
+                    <span className="text-accent-2">{formatTokens(stats.total_tokens)}</span>
+                    <span className="text-secondary">{stats.total_calls} calls</span>
+                  </div>
+                </div>
+              ))}
+            </div>
+          </details>
+        )}
+
+        {/* Daily Spend - Collapsible detail */}
+        {dailyCosts.length > 0 && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("📊", "#")} Daily Spend — last {dailyCosts.length} days
+            </summary>
+            <div className="mt-3">
+              <DailyChart series={dailyCosts} />
+            </div>
+          </details>
+        )}
+
+        {/* Cost by weekday this month - Collapsible detail */}
+        {data.weekday_costs.some((cost) => cost > 0) && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("📅", "#")} Cost by Weekday (This Month)
+            </summary>
+            <div className="mt-3">
+              <WeekdayChart costs={data.weekday_costs} />
+            </div>
+          </details>
+        )}
+
+        {/* Usage Heatmap - Collapsible detail */}
+        {heatmap && (
+          <details className="card">
+            <summary className="text-xs font-semibold text-secondary cursor-pointer hover:text-primary transition-colors">
+              {ic("🔥", "#")} Usage Heatmap — weekday x hour
+            </summary>
+            <div className="mt-3">
+              <Heatmap grid={heatmap} />
+            </div>
+          </details>
+        )}
+
+        </>
+        )}
+
+        {/* Footer */}
+        <footer className="text-center text-xs text-secondary opacity-50">
+          Claude Dashboard v0.8.4 • {selected_plan.name}
+          {data.auto_switched_from && ` (auto-switched from ${data.auto_switched_from})`} • {themes[currentTheme]?.name}
+          {copied && <span className="text-success"> • Copied: {copied}</span>}
+          {configReloaded && !data.config_error && <span className="text-success"> • Config reloaded</span>}
+          {data.config_error && (
+            <span className="text-warning" title={data.config_error}> • Config invalid, keeping the previous one</span>
+          )}
+          {data.skipped_files > 0 && (
+            <span className="text-warning"> • {data.skipped_files} file{data.skipped_files === 1 ? '' : 's'} unreadable</span>
+          )}
+        </footer>
+      </div>
+    </FormatContext.Provider>
   );
 }
 
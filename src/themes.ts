@@ -360,6 +360,23 @@ export const themes: Record<string, Theme> = {
       error: "#ff4444",
     },
   },
+  colorblind: {
+    name: "Color-blind Safe",
+    icon: "👁️",
+    colors: {
+      bgPrimary: "#121212",
+      bgSecondary: "#1e1e1e",
+      bgTertiary: "#2a2a2a",
+      textPrimary: "#f0f0f0",
+      textSecondary: "#a8a8a8",
+      accent1: "#56b4e9", // Sky blue (Okabe-Ito)
+      accent2: "#cc79a7", // Reddish purple
+      accent3: "#f0e442", // Yellow
+      success: "#0072b2", // Blue instead of green
+      warning: "#e69f00", // Orange
+      error: "#d55e00", // Vermillion
+    },
+  },
 };
 
 export const themeKeys = Object.keys(themes);
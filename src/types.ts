@@ -2,11 +2,16 @@
 
 export interface ModelStats {
   model: string;
+  tier: string;
   input_tokens: number;
   output_tokens: number;
   cache_create_tokens: number;
   cache_read_tokens: number;
   call_count: number;
+  cost: number;
+  cache_hit_percent: number;
+  cache_savings: number;
+  cost_per_output_mtok: number;
 }
 
 export interface PeriodStats {
@@ -14,8 +19,15 @@ export interface PeriodStats {
   total_tokens: number;
   total_cost: number;
   total_calls: number;
+  limit_cost: number;
+  limit_tokens: number;
   session_count: number;
   period_label: string;
+  first_timestamp: string | null;
+  last_timestamp: string | null;
+  cost_per_output_mtok: number;
+  avg_cost_per_session: number;
+  avg_tokens_per_session: number;
 }
 
 export interface PlanLimits {
@@ -23,8 +35,24 @@ export interface PlanLimits {
   token_limit: number;
   cost_limit: number;
   message_limit: number;
+  tier_cost_limits: Record<string, number>;
+  weekly_cost_limit: number; // 0 = none
+  weekly_token_limit: number; // 0 = none
 }
 
+// One tier's block cost against its own cap
+export interface TierLimitUsage {
+  tier: string;
+  limit_cost: number;
+  cost_limit: number;
+  percent: number;
+}
+
+// Projected exhaustion vs block reset
+export type ResetVerdict =
+  | { kind: "WillExhaust"; before_secs: number }
+  | { kind: "WillSurvive" };
+
 export interface CurrentBlockInfo {
   // Block timing
   block_start: string | null;
@@ -44,6 +72,7 @@ export interface CurrentBlockInfo {
   cost_percent: number;
   tokens_percent: number;
   messages_percent: number;
+  tier_limits: TierLimitUsage[];
 
   // Burn rate
   tokens_per_min: number;
@@ -53,9 +82,48 @@ export interface CurrentBlockInfo {
   // Predictions
   tokens_exhausted_at: string | null;
   cost_exhausted_at: string | null;
+  verdict: ResetVerdict;
+
+  // Live call (newest entry within the recency window)
+  live_call: boolean;
+  live_call_cost: number;
+  hourly_tokens: number[];
 
   // Status
   is_active: boolean;
+  last_block_end: string | null;
+}
+
+export interface BlockSummary {
+  start_time: string;
+  end_time: string;
+  is_active: boolean;
+  limit_cost: number;
+  limit_tokens: number;
+  messages: number;
+}
+
+export interface WeeklyBlockInfo {
+  week_start: string | null;
+  reset_time: string | null;
+  secs_until_reset: number;
+  limit_cost: number;
+  limit_tokens: number;
+  limit_messages: number;
+  cost_limit: number; // [week_reset] cost_limit, 0 = no cap
+  cost_percent: number;
+}
+
+// Rolling 7-day usage against the plan's weekly caps
+export interface WeeklyLimitInfo {
+  enabled: boolean;
+  limit_cost: number;
+  limit_tokens: number;
+  cost_limit: number;
+  token_limit: number;
+  cost_percent: number;
+  tokens_percent: number;
+  reset_estimate: string | null;
 }
 
 export interface ModelDistribution {
@@ -65,6 +133,59 @@ export interface ModelDistribution {
   tokens: number;
   cost: number;
   percent: number;
+  limit_percent: number;
+}
+
+export interface WarningThresholds {
+  warn_percent: number;
+  critical_percent: number;
+  soft_cost_limit: number | null;
+  soft_token_limit: number | null;
+}
+
+export interface EmptyState {
+  data_dir: string;
+  missing: boolean;
+}
+
+export interface CurrencySettings {
+  symbol: string;
+  rate: number;
+  decimal_separator: string;
+}
+
+export interface TokenFormat {
+  decimals: number;
+  raw: boolean;
+}
+
+export interface CreditSettings {
+  enabled: boolean;
+  per_dollar: number;
+  unit: string;
+}
+
+export interface TierCost {
+  tier: string;
+  cost: number;
+  calls: number;
+}
+
+// Downgrades to a lower tier within sessions (Opus -> Sonnet)
+export interface TierFallbacks {
+  fallback_count: number;
+  timestamps: string[];
+}
+
+export interface Trends {
+  today_of_week_percent: number;
+  week_of_month_percent: number;
+  week_daily_avg: number;
+  month_daily_avg: number;
+  accelerating: boolean;
+  projected_end_of_day: number;
+  projected_end_of_month: number;
+  projected_month_bill: number;
 }
 
 export interface DashboardData {
@@ -72,7 +193,36 @@ export interface DashboardData {
   today: PeriodStats;
   week: PeriodStats;
   month: PeriodStats;
+  all_time: PeriodStats;
+  yesterday: PeriodStats;
+  last_week: PeriodStats;
   selected_plan: PlanLimits;
+  auto_switched_from: string | null;
   model_distribution: ModelDistribution[];
+  model_version_distribution: ModelDistribution[];
+  block_history: BlockSummary[];
+  block_costs: number[]; // cents, oldest first
+  maxed_blocks: string[]; // block starts this month that hit the limit
+  weekly: WeeklyBlockInfo;
+  weekly_limits: WeeklyLimitInfo;
+  projects: [string, PeriodStats][];
+  tier_breakdown: TierCost[];
+  tier_fallbacks: TierFallbacks;
+  weekday_costs: number[];
+  sessions: [string, PeriodStats][];
+  trends: Trends;
+  costliest_call_today: [string, string, number] | null;
+  costliest_call_month: [string, string, number] | null;
+  timezone: string | null;
+  ascii: boolean;
+  credits: CreditSettings | null;
+  currency: CurrencySettings;
+  token_format: TokenFormat;
+  thresholds: WarningThresholds;
+  empty_state: EmptyState | null;
+  skipped_files: number;
+  snapshot: boolean;
+  config_reloaded_at: string | null;
+  config_error: string | null; // edited config rejected, previous one in use
   warnings: string[];
 }